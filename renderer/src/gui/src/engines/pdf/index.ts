@@ -13,6 +13,35 @@ class PDFEngine {
   render_page(page: number, transform: Float32Array) {
   }
 
+  get_layers(): { name: string, visible: boolean }[] {
+    return [];
+  }
+
+  set_layer_visible(index: number, visible: boolean) {
+  }
+
+  get_diagnostics(): {
+    kind: string,
+    severity: 'info' | 'warning' | 'error',
+    message: string,
+    offset: number | null,
+    operator: string | null,
+    stream: string | null,
+  }[] {
+    return [];
+  }
+
+  get_page_fonts(page: number): {
+    resource: string,
+    name: string,
+    type: string,
+    descendantType: 'CIDFontType0' | 'CIDFontType2' | null,
+    format: 'type1' | 'truetype' | 'type1c' | 'cidfonttype0c' | 'opentype' | null,
+    data: Uint8Array | null,
+  }[] {
+    return [];
+  }
+
   free() {
   }
 }
@@ -1,16 +1,26 @@
 // src/engines/pdf/src/content/mod.rs
-use lopdf::{Document, Object};
+use lopdf::{Document, Dictionary, Object, ObjectId};
 use crate::parser::{PDFResources, PDFError};
+use crate::parser::color::ColorSpace;
+use crate::parser::diagnostics::{Diagnostics, DiagnosticKind, Severity};
+use crate::parser::filters::decode_stream;
 use crate::text::FontManager;
-use crate::parser::content::ContentParser;
+use crate::parser::content::{ContentParser, ParsedContent};
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::WebGl2RenderingContext as GL;
-use flate2::read::ZlibDecoder;
-use std::io::Read;
 
+#[derive(Default)]
 pub struct PDFContent {
     pub text_objects: Vec<TextObject>,
     pub vector_objects: Vec<VectorObject>,
+    pub image_objects: Vec<ImageObject>,
+    pub shading_objects: Vec<ShadingObject>,
+    pub clip_paths: Vec<ClipPath>,
+    pub marked_content: Vec<MarkedContent>,
+    pub patterns: Vec<Pattern>,
+    pub glyph_procedures: Vec<GlyphProcedure>,
+    /// Problems found while loading and parsing the page.
+    pub diagnostics: Diagnostics,
     pub width: f32,
     pub height: f32,
     resources: PDFResources,
@@ -18,20 +28,471 @@ pub struct PDFContent {
 }
 
 pub struct TextObject {
+    /// The extracted text, which may be empty or blank when the glyphs
+    /// have no Unicode mapping.
     pub text: String,
     pub x: f32,
     pub y: f32,
     pub font_size: f32,
     pub font_name: String,
+    pub glyphs: Vec<Glyph>,
+    pub fill_color: [f32; 4],
+    pub stroke_color: [f32; 4],
+    /// Index into `PDFContent::patterns` painting the glyphs instead of
+    /// `fill_color`.
+    pub fill_pattern: Option<usize>,
+    pub stroke_pattern: Option<usize>,
+    pub render_mode: TextRenderMode,
+    pub horizontal_scaling: f32,
+    pub rise: f32,
+    pub blend_mode: BlendMode,
+    pub soft_mask: Option<SoftMask>,
+    /// Index into `PDFContent::clip_paths` of the clip in effect.
+    pub clip: Option<usize>,
+    /// Index into `PDFContent::marked_content` of the innermost enclosing
+    /// marked-content sequence.
+    pub marked_content: Option<usize>,
+}
+
+/// How glyph outlines are painted (`Tr`). `Invisible` is used by OCR text
+/// layers placed over scanned images.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextRenderMode {
+    Fill,
+    Stroke,
+    FillAndStroke,
+    Invisible,
+    FillAndClip,
+    StrokeAndClip,
+    FillStrokeAndClip,
+    Clip,
+}
+
+impl TextRenderMode {
+    pub fn from_code(code: i32) -> Option<Self> {
+        let mode = match code {
+            0 => TextRenderMode::Fill,
+            1 => TextRenderMode::Stroke,
+            2 => TextRenderMode::FillAndStroke,
+            3 => TextRenderMode::Invisible,
+            4 => TextRenderMode::FillAndClip,
+            5 => TextRenderMode::StrokeAndClip,
+            6 => TextRenderMode::FillStrokeAndClip,
+            7 => TextRenderMode::Clip,
+            _ => return None,
+        };
+        Some(mode)
+    }
+}
+
+/// A single shown glyph with its origin and advance in user space.
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    pub code: u32,
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub advance: f32,
+    /// For Type 0 fonts, the glyph's index in the embedded font program.
+    pub glyph_id: Option<u32>,
+    /// For Type 3 fonts, the index into `PDFContent::glyph_procedures` of
+    /// the procedure drawing this glyph, and the matrix mapping its glyph
+    /// space to user space.
+    pub procedure: Option<(usize, Matrix)>,
 }
 
 pub struct VectorObject {
     pub path_data: Vec<PathCommand>,
     pub stroke_color: [f32; 4],
     pub fill_color: Option<[f32; 4]>,
+    /// Index into `PDFContent::patterns` painting the path instead of the
+    /// flat color.
+    pub fill_pattern: Option<usize>,
+    pub stroke_pattern: Option<usize>,
+    pub stroked: bool,
+    pub fill_rule: FillRule,
+    pub line_style: LineStyle,
+    pub rendering_intent: RenderingIntent,
+    pub flatness: f32,
+    pub blend_mode: BlendMode,
+    pub soft_mask: Option<SoftMask>,
+    /// Index into `PDFContent::clip_paths` of the clip in effect.
+    pub clip: Option<usize>,
+    /// Index into `PDFContent::marked_content` of the innermost enclosing
+    /// marked-content sequence.
+    pub marked_content: Option<usize>,
+}
+
+/// An image painted by `Do`. The image fills the unit square of image
+/// space, which `matrix` maps to user space; its first row is at the top.
+pub struct ImageObject {
+    pub width: u32,
+    pub height: u32,
+    /// `None` for stencil masks, which are painted in the fill color.
+    pub color_space: Option<ColorSpace>,
+    pub bits_per_component: u8,
+    pub data: ImageData,
+    pub matrix: Matrix,
+    pub mask: Option<ImageMask>,
+    pub interpolate: bool,
+    pub alpha: f32,
+    pub blend_mode: BlendMode,
+    pub soft_mask: Option<SoftMask>,
+    /// Index into `PDFContent::clip_paths` of the clip in effect.
+    pub clip: Option<usize>,
+    /// Index into `PDFContent::marked_content` of the innermost enclosing
+    /// marked-content sequence.
+    pub marked_content: Option<usize>,
+}
+
+/// Pixel data of an image, either decoded or in a compressed format the
+/// browser can decode itself.
+#[derive(Debug, Clone)]
+pub enum ImageData {
+    /// 8-bit RGBA samples, row by row.
+    Rgba(Vec<u8>),
+    Jpeg(Vec<u8>),
+    Jpeg2000(Vec<u8>),
+}
+
+/// Opacity of an image from `/SMask` or a stencil `/Mask`. Its size may
+/// differ from the image's, in which case it is stretched over the same
+/// unit square.
+#[derive(Debug, Clone)]
+pub struct ImageMask {
+    pub width: u32,
+    pub height: u32,
+    /// One 8-bit alpha value per pixel.
+    pub alpha: Vec<u8>,
+}
+
+/// A shading painted by `sh`, filling the current clip.
+pub struct ShadingObject {
+    pub shading: Shading,
+    pub alpha: f32,
+    pub blend_mode: BlendMode,
+    pub soft_mask: Option<SoftMask>,
+    /// Index into `PDFContent::clip_paths` of the clip in effect.
+    pub clip: Option<usize>,
+    /// Index into `PDFContent::marked_content` of the innermost enclosing
+    /// marked-content sequence.
+    pub marked_content: Option<usize>,
+}
+
+/// A smooth color transition, decoded into geometry a renderer can draw
+/// without evaluating PDF functions.
+#[derive(Debug, Clone)]
+pub struct Shading {
+    pub geometry: ShadingGeometry,
+    /// Maps shading space, in which the geometry is given, to user space.
+    pub matrix: Matrix,
+    /// Clip rectangle `[x0 y0 x1 y1]` in shading space.
+    pub bbox: Option<[f32; 4]>,
+    /// Color outside the shaded area, used only by shading patterns.
+    pub background: Option<[f32; 4]>,
+    pub anti_alias: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ShadingGeometry {
+    /// Color varies along the axis from `start` to `end`; `extend` continues
+    /// the end colors beyond either point.
+    Axial {
+        start: (f32, f32),
+        end: (f32, f32),
+        stops: Vec<GradientStop>,
+        extend: (bool, bool),
+    },
+    /// Color varies between two circles given as `(x, y, radius)`.
+    Radial {
+        start: (f32, f32, f32),
+        end: (f32, f32, f32),
+        stops: Vec<GradientStop>,
+        extend: (bool, bool),
+    },
+    /// Gouraud-shaded triangles. Function-based, lattice and patch
+    /// shadings are approximated this way.
+    Mesh(Vec<[ShadingVertex; 3]>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    /// Position between 0 (`start`) and 1 (`end`).
+    pub offset: f32,
+    pub color: [f32; 4],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadingVertex {
+    pub x: f32,
+    pub y: f32,
+    pub color: [f32; 4],
+}
+
+/// The content stream drawing a glyph of a Type 3 font.
+pub struct GlyphProcedure {
+    pub font_name: String,
+    pub code: u32,
+    /// Horizontal displacement declared by `d0` or `d1`, in glyph space.
+    /// Text is positioned using the font's `/Widths` instead.
+    pub width: f32,
+    /// Glyph bounds `[llx lly urx ury]` declared by `d1`. Such glyphs are
+    /// shapes painted in the color of the text showing them, and colors set
+    /// within `content` are ignored.
+    pub bbox: Option<[f32; 4]>,
+    /// Objects of the glyph procedure, in glyph space.
+    pub content: ParsedContent,
+}
+
+/// Paint selected with `scn`/`SCN` in the Pattern color space.
+pub enum Pattern {
+    Tiling(TilingPattern),
+    /// The shading's matrix combines the pattern matrix with the pattern
+    /// space of the content stream that selected it.
+    Shading(Shading),
+}
+
+/// A pattern cell repeated at fixed intervals to fill an area.
+pub struct TilingPattern {
+    pub paint_type: PaintType,
+    pub tiling_type: TilingType,
+    /// Cell bounds `[x0 y0 x1 y1]` in pattern space, clipping each tile.
+    pub bbox: [f32; 4],
+    pub x_step: f32,
+    pub y_step: f32,
+    /// Maps pattern space to user space, including the pattern space of
+    /// the content stream that selected the pattern.
+    pub matrix: Matrix,
+    /// Objects of the cell's content stream, in pattern space.
+    pub cell: ParsedContent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaintType {
+    /// The cell specifies its own colors.
+    Colored,
+    /// The cell is a stencil painted in the fill or stroke color of the
+    /// object using the pattern; colors set within the cell are ignored.
+    Uncolored,
+}
+
+impl PaintType {
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            1 => Some(PaintType::Colored),
+            2 => Some(PaintType::Uncolored),
+            _ => None,
+        }
+    }
+}
+
+/// How tile positions may be adjusted to the device pixel grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TilingType {
+    ConstantSpacing,
+    NoDistortion,
+    ConstantSpacingFaster,
+}
+
+impl TilingType {
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            1 => Some(TilingType::ConstantSpacing),
+            2 => Some(TilingType::NoDistortion),
+            3 => Some(TilingType::ConstantSpacingFaster),
+            _ => None,
+        }
+    }
+}
+
+/// Stroke parameters, with lengths in the same space as `path_data`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineStyle {
+    /// Zero requests the thinnest line the device can render.
+    pub width: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub miter_limit: f32,
+    /// Alternating dash and gap lengths; empty for a solid line.
+    pub dash_array: Vec<f32>,
+    pub dash_phase: f32,
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        LineStyle {
+            width: 1.0,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 10.0,
+            dash_array: Vec::new(),
+            dash_phase: 0.0,
+        }
+    }
+}
+
+impl LineStyle {
+    /// Scales the lengths by the average scale factor of `matrix`, as
+    /// stroking happens in user space before the CTM is applied.
+    pub fn transformed(&self, matrix: &Matrix) -> LineStyle {
+        let scale = (matrix.a * matrix.d - matrix.b * matrix.c).abs().sqrt();
+        LineStyle {
+            width: self.width * scale,
+            dash_array: self.dash_array.iter().map(|length| length * scale).collect(),
+            dash_phase: self.dash_phase * scale,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+impl LineCap {
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(LineCap::Butt),
+            1 => Some(LineCap::Round),
+            2 => Some(LineCap::Square),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl LineJoin {
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(LineJoin::Miter),
+            1 => Some(LineJoin::Round),
+            2 => Some(LineJoin::Bevel),
+            _ => None,
+        }
+    }
+}
+
+/// Separable and non-separable blend modes from the `/BM` entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mode = match name {
+            "Normal" | "Compatible" => BlendMode::Normal,
+            "Multiply" => BlendMode::Multiply,
+            "Screen" => BlendMode::Screen,
+            "Overlay" => BlendMode::Overlay,
+            "Darken" => BlendMode::Darken,
+            "Lighten" => BlendMode::Lighten,
+            "ColorDodge" => BlendMode::ColorDodge,
+            "ColorBurn" => BlendMode::ColorBurn,
+            "HardLight" => BlendMode::HardLight,
+            "SoftLight" => BlendMode::SoftLight,
+            "Difference" => BlendMode::Difference,
+            "Exclusion" => BlendMode::Exclusion,
+            "Hue" => BlendMode::Hue,
+            "Saturation" => BlendMode::Saturation,
+            "Color" => BlendMode::Color,
+            "Luminosity" => BlendMode::Luminosity,
+            _ => return None,
+        };
+        Some(mode)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoftMaskType {
+    Alpha,
+    Luminosity,
+}
+
+/// A soft mask whose values come from rendering the transparency group
+/// form XObject `group`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftMask {
+    pub subtype: SoftMaskType,
+    pub group: ObjectId,
+    /// Backdrop color for luminosity masks, in the group's color space.
+    pub backdrop: Vec<f32>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderingIntent {
+    AbsoluteColorimetric,
+    RelativeColorimetric,
+    Saturation,
+    Perceptual,
+}
+
+impl RenderingIntent {
+    /// Unrecognised intents fall back to relative colorimetric, as the
+    /// specification requires.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "AbsoluteColorimetric" => RenderingIntent::AbsoluteColorimetric,
+            "Saturation" => RenderingIntent::Saturation,
+            "Perceptual" => RenderingIntent::Perceptual,
+            _ => RenderingIntent::RelativeColorimetric,
+        }
+    }
+}
+
+/// A clipping path in user space. The effective clip region is the
+/// intersection of this path with its parent chain.
+#[derive(Debug, Clone)]
+pub struct ClipPath {
+    pub path_data: Vec<PathCommand>,
+    pub fill_rule: FillRule,
+    pub parent: Option<usize>,
+}
+
+/// A marked-content sequence (`BMC`/`BDC` ... `EMC`) or point (`MP`/`DP`).
+#[derive(Debug, Clone)]
+pub struct MarkedContent {
+    pub tag: String,
+    /// Property list, given inline or named from the `/Properties` resource.
+    pub properties: Option<Dictionary>,
+    /// Index of the enclosing sequence.
+    pub parent: Option<usize>,
+    /// Points mark a position in the stream and enclose no objects.
+    pub point: bool,
+}
+
+/// Rule used to decide which regions of a filled path are inside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+#[derive(Debug, Clone)]
 pub enum PathCommand {
     MoveTo(f32, f32),
     LineTo(f32, f32),
@@ -39,6 +500,9 @@ pub enum PathCommand {
     Close,
 }
 
+/// Affine transform `[a b c d e f]` mapping `(x, y)` to
+/// `(a*x + c*y + e, b*x + d*y + f)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Matrix {
     pub a: f32,
     pub b: f32,
@@ -58,151 +522,139 @@ impl Default for Matrix {
     }
 }
 
-impl PDFContent {
-    fn decompress_stream(data: &[u8]) -> Option<Vec<u8>> {
-        let mut decoder = ZlibDecoder::new(data);
-        let mut decompressed = Vec::new();
-        match decoder.read_to_end(&mut decompressed) {
-            Ok(_) => {
-                web_sys::console::log_1(&format!(
-                    "Successfully decompressed stream from {} to {} bytes",
-                    data.len(),
-                    decompressed.len()
-                ).into());
-                Some(decompressed)
-            },
-            Err(e) => {
-                web_sys::console::log_1(&format!("Failed to decompress stream: {}", e).into());
-                None
-            }
+impl Matrix {
+    pub fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Self {
+        Matrix { a, b, c, d, e, f }
+    }
+
+    pub fn translate(tx: f32, ty: f32) -> Self {
+        Matrix::new(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    /// Returns the transform that applies `self` first and then `other`,
+    /// matching the PDF convention `M' = self × other`.
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * other.a + self.b * other.c,
+            b: self.a * other.b + self.b * other.d,
+            c: self.c * other.a + self.d * other.c,
+            d: self.c * other.b + self.d * other.d,
+            e: self.e * other.a + self.f * other.c + other.e,
+            f: self.e * other.b + self.f * other.d + other.f,
         }
     }
 
+    pub fn transform_point(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// Length of the transformed unit vertical vector, used to scale sizes
+    /// such as font heights into the target space.
+    pub fn vertical_scale(&self) -> f32 {
+        (self.c * self.c + self.d * self.d).sqrt()
+    }
+}
+
+impl PDFContent {
+    /// Decodes one of the page's content streams, recording failures.
+    fn decode_contents(stream: &lopdf::Stream, diagnostics: &mut Diagnostics) -> Vec<u8> {
+        decode_stream(stream).unwrap_or_else(|e| {
+            diagnostics.report(DiagnosticKind::DecodeFailed, Severity::Error, format!(
+                "Failed to decode page contents: {:?}", e
+            ));
+            Vec::new()
+        })
+    }
+
     pub fn from_page(doc: &Document, page_num: u32) -> Option<Self> {
-        web_sys::console::log_1(&format!("Attempting to get page {}", page_num).into());
-        
+        let mut diagnostics = Diagnostics::default();
+
         // Get page reference from pages map
         let pages = doc.get_pages();
         let page_id = pages.get(&page_num)?;
-        web_sys::console::log_1(&format!("Found page ID: {:?}", page_id).into());
         
         // Get page dictionary
         if let Ok(page_dict) = doc.get_dictionary(*page_id) {
-            web_sys::console::log_1(&"Got page dictionary".into());
-            web_sys::console::log_1(&format!("Page dictionary contents: {:?}", page_dict).into());
-            
-            // Get page resources - try different ways
-            let resources_dict = match page_dict.get(b"Resources").ok()? {
-                Object::Reference(id) => {
-                    web_sys::console::log_1(&format!("Resources is a reference: {:?}", id).into());
-                    doc.get_dictionary(*id).ok()?
-                },
-                Object::Dictionary(dict) => {
-                    web_sys::console::log_1(&"Resources is a direct dictionary".into());
-                    dict
-                },
-                other => {
-                    web_sys::console::log_1(&format!("Resources has unexpected type: {:?}", other).into());
-                    return None;
-                }
-            };
-            
-            let resources = PDFResources::new(doc, resources_dict).ok()?;
+            let resources_dict = Self::resources_dictionary(doc, page_dict)?;
+            let mut resources = PDFResources::new(doc, resources_dict).ok()?;
+            diagnostics.extend(resources.take_diagnostics());
             
             // Get page contents - handle both single stream and array of streams
             let contents = match page_dict.get(b"Contents").ok()? {
                 Object::Reference(id) => {
                     if let Ok(Object::Stream(stream)) = doc.get_object(*id) {
-                        // Check if stream is compressed
-                        if let Ok(filter) = stream.filter() {
-                            if filter.as_str() == "FlateDecode" {
-                                Self::decompress_stream(&stream.content)?
-                            } else {
-                                web_sys::console::log_1(&format!("Unknown filter: {}", filter).into());
-                                stream.content.clone()
-                            }
-                        } else {
-                            stream.content.clone()
-                        }
+                        Self::decode_contents(stream, &mut diagnostics)
                     } else {
-                        web_sys::console::log_1(&"Contents reference is not a stream".into());
                         return None;
                     }
                 },
                 Object::Array(arr) => {
-                    // Concatenate multiple content streams
+                    // Concatenate multiple content streams; a separator
+                    // keeps tokens at stream boundaries apart
                     let mut combined = Vec::new();
                     for item in arr {
                         if let Object::Reference(id) = item {
                             if let Ok(Object::Stream(stream)) = doc.get_object(*id) {
-                                let content = if let Ok(filter) = stream.filter() {
-                                    if filter.as_str() == "FlateDecode" {
-                                        Self::decompress_stream(&stream.content)?
-                                    } else {
-                                        web_sys::console::log_1(&format!("Unknown filter: {}", filter).into());
-                                        stream.content.clone()
-                                    }
-                                } else {
-                                    stream.content.clone()
-                                };
-                                combined.extend_from_slice(&content);
+                                combined.extend_from_slice(&Self::decode_contents(stream, &mut diagnostics));
+                                combined.push(b'\n');
                             }
                         }
                     }
                     combined
                 },
-                other => {
-                    web_sys::console::log_1(&format!("Contents has unexpected type: {:?}", other).into());
-                    return None;
-                }
+                _ => return None,
             };
             
-            // Print a preview of the content stream
-            let preview = String::from_utf8_lossy(&contents[..contents.len().min(100)]);
-            web_sys::console::log_1(&format!("Content stream preview: {}", preview).into());
-            
             // Get page dimensions from MediaBox
             let media_box = match page_dict.get(b"MediaBox").ok()? {
                 Object::Array(arr) => arr,
-                other => {
-                    web_sys::console::log_1(&format!("MediaBox has unexpected type: {:?}", other).into());
-                    return None;
-                }
+                _ => return None,
             };
             
             let get_num = |idx: usize| -> Option<f32> {
                 match &media_box[idx] {
                     Object::Integer(n) => Some(*n as f32),
                     Object::Real(n) => Some(*n),
-                    other => {
-                        web_sys::console::log_1(&format!("MediaBox value has unexpected type: {:?}", other).into());
-                        None
-                    }
+                    _ => None,
                 }
             };
             
             let width = get_num(2)? - get_num(0)?;
             let height = get_num(3)? - get_num(1)?;
-            web_sys::console::log_1(&format!("Page dimensions: {}x{}", width, height).into());
-            
-            // Parse content stream
-            let text_objects = Vec::new();
-            let vector_objects = Vec::new();
             
+            // The objects are filled in once the content stream is parsed
             return Some(PDFContent {
-                text_objects,
-                vector_objects,
+                diagnostics,
                 width,
                 height,
                 resources,
                 content_stream: contents,
+                ..Default::default()
             });
-        } else {
-            web_sys::console::log_1(&"Failed to get page dictionary".into());
         }
         None
     }
 
+    /// Loads the resources of a page without decoding its content, e.g. to
+    /// list its fonts.
+    pub fn page_resources(doc: &Document, page_num: u32) -> Option<PDFResources> {
+        let page_id = *doc.get_pages().get(&page_num)?;
+        let page_dict = doc.get_dictionary(page_id).ok()?;
+        PDFResources::new(doc, Self::resources_dictionary(doc, page_dict)?).ok()
+    }
+
+    /// The `/Resources` of a page, given directly or by reference.
+    fn resources_dictionary<'a>(doc: &'a Document, page_dict: &'a Dictionary) -> Option<&'a Dictionary> {
+        match page_dict.get(b"Resources").ok()? {
+            Object::Reference(id) => doc.get_dictionary(*id).ok(),
+            Object::Dictionary(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
     pub fn get_resources(&self) -> Result<&PDFResources, JsValue> {
         Ok(&self.resources)
     }
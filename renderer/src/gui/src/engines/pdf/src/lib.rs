@@ -1,9 +1,10 @@
 // src/engines/pdf/src/lib.rs
 use wasm_bindgen::prelude::*;
 use lopdf::Document;
-use web_sys::console;
 use std::panic;
 use crate::parser::content::ContentParser;
+use crate::parser::optional_content::OptionalContent;
+use crate::parser::diagnostics::Diagnostics;
 
 mod content;
 mod parser;
@@ -22,6 +23,9 @@ pub fn start() {
 #[wasm_bindgen]
 pub struct PDFEngine {
     document: Option<Document>,
+    optional_content: OptionalContent,
+    // Diagnostics of the most recently rendered page
+    diagnostics: Diagnostics,
     current_page: u32,
     renderer: renderer::WebGLRenderer,
     viewport: viewport::ViewportManager,
@@ -31,8 +35,6 @@ pub struct PDFEngine {
 impl PDFEngine {
     #[wasm_bindgen(constructor)]
     pub fn new(canvas_id: &str) -> Result<PDFEngine, JsValue> {
-        console::log_1(&"Initializing PDF Engine".into());
-        
         let renderer = renderer::WebGLRenderer::new(canvas_id)?;
         let viewport = viewport::ViewportManager::new();
         
@@ -40,6 +42,8 @@ impl PDFEngine {
             viewport,
             renderer,
             document: None,
+            optional_content: OptionalContent::default(),
+            diagnostics: Diagnostics::default(),
             current_page: 0,
         })
     }
@@ -48,7 +52,7 @@ impl PDFEngine {
     pub fn load_document(&mut self, data: &[u8]) -> Result<(), JsValue> {
         match Document::load_from(data) {
             Ok(doc) => {
-                console::log_1(&"PDF document loaded successfully".into());
+                self.optional_content = OptionalContent::from_document(&doc);
                 self.document = Some(doc);
                 Ok(())
             }
@@ -67,17 +71,27 @@ impl PDFEngine {
                     let mut parser = ContentParser::new(
                         stream,
                         self.renderer.get_font_manager_mut()?,
-                        resources
+                        resources,
+                        doc,
+                        &self.optional_content,
                     );
                     
-                    if let Some((text_objects, vector_objects)) = parser.parse() {
-                        content.text_objects = text_objects;
-                        content.vector_objects = vector_objects;
+                    if let Some(parsed) = parser.parse() {
+                        content.text_objects = parsed.text_objects;
+                        content.vector_objects = parsed.vector_objects;
+                        content.image_objects = parsed.image_objects;
+                        content.shading_objects = parsed.shading_objects;
+                        content.clip_paths = parsed.clip_paths;
+                        content.marked_content = parsed.marked_content;
+                        content.patterns = parsed.patterns;
+                        content.glyph_procedures = parsed.glyph_procedures;
+                        content.diagnostics.extend(parsed.diagnostics);
                     }
 
                     self.viewport.set_page_size(content.width, content.height);
                     let transform = self.viewport.get_transform_matrix();
                     self.renderer.render_page_content(&content, &transform)?;
+                    self.diagnostics = content.diagnostics;
                     self.current_page = page_num;
                     Ok(())
                 }
@@ -89,11 +103,6 @@ impl PDFEngine {
     }
 
     fn multiply_matrices(a: &[f32; 16], b: &[f32]) -> [f32; 16] {
-        web_sys::console::log_1(&format!(
-            "Multiplying matrices:\nA: {:?}\nB: {:?}",
-            a, b
-        ).into());
-
         let mut result = [0.0; 16];
         for i in 0..4 {
             for j in 0..4 {
@@ -105,11 +114,6 @@ impl PDFEngine {
             }
         }
 
-        web_sys::console::log_1(&format!(
-            "Result matrix: {:?}",
-            result
-        ).into());
-
         result
     }
 
@@ -126,6 +130,79 @@ impl PDFEngine {
     pub fn get_current_page(&self) -> u32 {
         self.current_page
     }
+
+    /// Lists the document's optional content groups as `{ name, visible }`
+    /// objects, in the order used by `set_layer_visible`.
+    #[wasm_bindgen]
+    pub fn get_layers(&self) -> Result<js_sys::Array, JsValue> {
+        let layers = js_sys::Array::new();
+        for layer in self.optional_content.layers() {
+            let entry = js_sys::Object::new();
+            js_sys::Reflect::set(&entry, &"name".into(), &layer.name.as_str().into())?;
+            js_sys::Reflect::set(&entry, &"visible".into(), &layer.visible.into())?;
+            layers.push(&entry);
+        }
+        Ok(layers)
+    }
+
+    /// Lists the problems found while rendering the current page as
+    /// `{ kind, severity, message, offset, operator, stream }` objects.
+    /// `stream` is the object reference of the form, pattern or glyph
+    /// procedure containing the operator, or `null` for the page contents.
+    #[wasm_bindgen]
+    pub fn get_diagnostics(&self) -> Result<js_sys::Array, JsValue> {
+        let diagnostics = js_sys::Array::new();
+        for diagnostic in self.diagnostics.iter() {
+            let entry = js_sys::Object::new();
+            let stream = diagnostic.stream.map(|(number, generation)| format!("{} {} R", number, generation));
+            js_sys::Reflect::set(&entry, &"kind".into(), &diagnostic.kind.as_str().into())?;
+            js_sys::Reflect::set(&entry, &"severity".into(), &diagnostic.severity.as_str().into())?;
+            js_sys::Reflect::set(&entry, &"message".into(), &diagnostic.message.as_str().into())?;
+            js_sys::Reflect::set(&entry, &"offset".into(), &diagnostic.offset.map(|offset| offset as u32).into())?;
+            js_sys::Reflect::set(&entry, &"operator".into(), &diagnostic.operator.as_deref().into())?;
+            js_sys::Reflect::set(&entry, &"stream".into(), &stream.into())?;
+            diagnostics.push(&entry);
+        }
+        Ok(diagnostics)
+    }
+
+    /// Lists the fonts used directly by a page as `{ resource, name, type,
+    /// descendantType, format, data }` objects. `descendantType` is the
+    /// CIDFont subtype of Type 0 fonts. `format` and `data` describe the
+    /// embedded font program and are `null` for fonts without one.
+    #[wasm_bindgen]
+    pub fn get_page_fonts(&self, page_num: u32) -> Result<js_sys::Array, JsValue> {
+        let doc = self.document.as_ref().ok_or_else(|| JsValue::from_str("No document loaded"))?;
+        // Only the resources are needed, not the decoded content
+        let resources = PDFContent::page_resources(doc, page_num)
+            .ok_or_else(|| JsValue::from_str("Failed to get page resources"))?;
+
+        let fonts = js_sys::Array::new();
+        for (resource, font) in resources.fonts() {
+            let entry = js_sys::Object::new();
+            let program = font.program.as_ref();
+            js_sys::Reflect::set(&entry, &"resource".into(), &resource.into())?;
+            js_sys::Reflect::set(&entry, &"name".into(), &font.name.as_str().into())?;
+            js_sys::Reflect::set(&entry, &"type".into(), &font.font_type.as_str().into())?;
+            let descendant = font.cid_font().map(|cid_font| cid_font.font_type.as_str());
+            js_sys::Reflect::set(&entry, &"descendantType".into(), &descendant.into())?;
+            js_sys::Reflect::set(&entry, &"format".into(), &program.map(|p| p.format.as_str()).into())?;
+            let data = program.map_or(JsValue::NULL, |p| js_sys::Uint8Array::from(p.data.as_slice()).into());
+            js_sys::Reflect::set(&entry, &"data".into(), &data)?;
+            fonts.push(&entry);
+        }
+        Ok(fonts)
+    }
+
+    /// Shows or hides a layer. Takes effect on the next `render_page`.
+    #[wasm_bindgen]
+    pub fn set_layer_visible(&mut self, index: u32, visible: bool) -> Result<(), JsValue> {
+        if self.optional_content.set_visible(index as usize, visible) {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&format!("No layer at index {}", index)))
+        }
+    }
 }
 
 impl Drop for PDFEngine {
@@ -0,0 +1,478 @@
+// src/engines/pdf/src/parser/cmap.rs
+use std::collections::HashMap;
+use lopdf::{Document, Object};
+use super::resolve;
+use super::encoding::glyph_to_unicode;
+use super::filters::decode_stream;
+use super::lexer::{Lexer, Operand, Token};
+
+/// Deepest chain of embedded CMaps extending each other through
+/// `/UseCMap`.
+const MAX_USECMAP_DEPTH: usize = 8;
+
+/// A codespace range: the codes as long as `low` and `high` whose bytes
+/// each lie between theirs. A string's next code is matched against the
+/// ranges of each byte length in turn, shortest first.
+#[derive(Debug, Clone)]
+struct CodespaceRange {
+    low: Vec<u8>,
+    high: Vec<u8>,
+}
+
+impl CodespaceRange {
+    fn contains(&self, bytes: &[u8]) -> bool {
+        bytes.len() == self.low.len()
+            && bytes.iter().zip(self.low.iter().zip(&self.high)).all(|(b, (low, high))| (low..=high).contains(&b))
+    }
+}
+
+/// Consecutive codes mapped to text whose last UTF-16 unit increments with
+/// the code.
+#[derive(Debug, Clone)]
+struct UnicodeRange {
+    low: u32,
+    high: u32,
+    start: Vec<u16>,
+}
+
+/// Consecutive codes mapped to consecutive CIDs starting at `cid`.
+#[derive(Debug, Clone)]
+struct CidRange {
+    low: u32,
+    high: u32,
+    cid: u32,
+}
+
+/// A CMap: the codespace that splits strings into codes, and either the
+/// CID of each code, for the encoding of a Type 0 font, or its text, for
+/// `/ToUnicode` CMaps.
+#[derive(Debug, Clone, Default)]
+pub struct CMap {
+    codespace: Vec<CodespaceRange>,
+    unicode: HashMap<u32, String>,
+    unicode_ranges: Vec<UnicodeRange>,
+    cids: HashMap<u32, u32>,
+    cid_ranges: Vec<CidRange>,
+    /// CIDs of codes with no other mapping, from `beginnotdefrange`.
+    notdef_ranges: Vec<CidRange>,
+    /// Codes are their own CIDs, as in `Identity-H`.
+    identity: bool,
+    /// Codes are UTF-16 text, as in the predefined `Uni…-UCS2` and
+    /// `Uni…-UTF16` CMaps.
+    unicode_codes: bool,
+    /// `/WMode 1`: glyphs are laid out top to bottom.
+    vertical: bool,
+    /// Name of a predefined CMap whose CIDs are not available, so that its
+    /// codes select CID 0.
+    unavailable: Option<String>,
+    /// The predefined CMap named by `usecmap`, until it is resolved.
+    use_cmap: Option<String>,
+    /// The CMap this one extends; its mappings apply to codes this one does
+    /// not map.
+    parent: Option<Box<CMap>>,
+}
+
+impl CMap {
+    /// Loads the CMap of a Type 0 font's `/Encoding`: the name of a
+    /// predefined CMap or an embedded CMap stream.
+    pub fn load(doc: &Document, object: &Object) -> Option<Self> {
+        Self::load_nested(doc, object, 0)
+    }
+
+    fn load_nested(doc: &Document, object: &Object, depth: usize) -> Option<Self> {
+        match resolve(doc, object) {
+            Object::Name(name) => Some(Self::predefined(std::str::from_utf8(name).ok()?)),
+            Object::Stream(stream) => {
+                let data = decode_stream(stream).ok()?;
+                let mut cmap = Self::parse(&data);
+                // The stream's `/UseCMap` entry takes precedence over a
+                // `usecmap` operator in its source
+                let use_cmap = cmap.use_cmap.take();
+                let parent = match stream.dict.get(b"UseCMap") {
+                    Ok(parent) if depth < MAX_USECMAP_DEPTH => Self::load_nested(doc, parent, depth + 1),
+                    Ok(_) => None,
+                    Err(_) => use_cmap.map(|name| Self::predefined(&name)),
+                };
+                if let Some(parent) = parent {
+                    cmap.extend(parent);
+                }
+                Some(cmap)
+            },
+            _ => None,
+        }
+    }
+
+    /// A predefined CMap. Only the identity CMaps map codes to CIDs; the
+    /// others need Adobe's CMap files, so they are given the codespace of
+    /// their encoding to split strings into codes, and are marked
+    /// unavailable. The text of Unicode CMaps' codes is still known.
+    pub fn predefined(name: &str) -> Self {
+        let range = |low: &[u8], high: &[u8]| CodespaceRange { low: low.to_vec(), high: high.to_vec() };
+        let mut cmap = CMap {
+            vertical: name.ends_with("-V"),
+            ..CMap::default()
+        };
+        let codespace: &[(&[u8], &[u8])] = match name {
+            "Identity-H" | "Identity-V" => {
+                cmap.identity = true;
+                &[(&[0x00, 0x00], &[0xFF, 0xFF])]
+            },
+            _ if name.starts_with("Uni") && name.contains("-UCS2-") => {
+                cmap.unicode_codes = true;
+                &[(&[0x00, 0x00], &[0xFF, 0xFF])]
+            },
+            _ if name.starts_with("Uni") && name.contains("-UTF16-") => {
+                cmap.unicode_codes = true;
+                &[
+                    (&[0x00, 0x00], &[0xD7, 0xFF]),
+                    (&[0xE0, 0x00], &[0xFF, 0xFF]),
+                    (&[0xD8, 0x00, 0xDC, 0x00], &[0xDB, 0xFF, 0xDF, 0xFF]),
+                ]
+            },
+            // Shift-JIS
+            _ if name.contains("RKSJ") => &[
+                (&[0x00], &[0x80]),
+                (&[0xA0], &[0xDF]),
+                (&[0x81, 0x40], &[0x9F, 0xFC]),
+                (&[0xE0, 0x40], &[0xFC, 0xFC]),
+            ],
+            // GBK and Unified Hangul Code
+            _ if name.starts_with("GBK") || name.contains("UHC") => &[
+                (&[0x00], &[0x80]),
+                (&[0x81, 0x40], &[0xFE, 0xFE]),
+            ],
+            // Big Five
+            _ if name.contains("B5") => &[
+                (&[0x00], &[0x80]),
+                (&[0xA1, 0x40], &[0xFE, 0xFE]),
+            ],
+            _ if name.contains("EUC") => &[
+                (&[0x00], &[0x80]),
+                (&[0x8E, 0xA0], &[0x8E, 0xDF]),
+                (&[0xA1, 0xA1], &[0xFE, 0xFE]),
+            ],
+            // Most CJK encodings read ASCII as single bytes and the rest
+            // as pairs
+            _ => &[
+                (&[0x00], &[0x7F]),
+                (&[0x80, 0x00], &[0xFF, 0xFF]),
+            ],
+        };
+        cmap.codespace = codespace.iter().map(|(low, high)| range(low, high)).collect();
+        if !cmap.identity {
+            cmap.unavailable = Some(name.to_string());
+        }
+        cmap
+    }
+
+    /// Makes this CMap extend `parent`, inheriting its codespace and the
+    /// mappings of codes it does not map itself.
+    fn extend(&mut self, parent: CMap) {
+        let own_codespace = std::mem::take(&mut self.codespace);
+        self.codespace = parent.codespace.iter().cloned().chain(own_codespace).collect();
+        self.vertical |= parent.vertical;
+        self.parent = Some(Box::new(parent));
+    }
+
+    /// Parses the PostScript source of a CMap. Malformed entries are
+    /// skipped rather than failing the whole map.
+    pub fn parse(data: &[u8]) -> Self {
+        let mut cmap = CMap::default();
+        let mut lexer = Lexer::new(data);
+        let mut operands = Vec::new();
+
+        while let Some(token) = lexer.next_token() {
+            let keyword = match token {
+                Token::Operand(operand) => {
+                    operands.push(operand);
+                    continue;
+                },
+                Token::Keyword(keyword) => keyword,
+            };
+            match keyword.as_str() {
+                "endcodespacerange" => {
+                    for entry in operands.chunks_exact(2) {
+                        if let [Operand::String(low), Operand::String(high)] = entry {
+                            if low.len() == high.len() && !low.is_empty() {
+                                cmap.codespace.push(CodespaceRange { low: low.clone(), high: high.clone() });
+                            }
+                        }
+                    }
+                },
+                "endbfchar" => {
+                    for entry in operands.chunks_exact(2) {
+                        let text = match &entry[1] {
+                            Operand::String(text) => Some(String::from_utf16_lossy(&utf16_units(text))),
+                            // Some producers give glyph names instead of text
+                            Operand::Name(name) => glyph_to_unicode(name),
+                            _ => None,
+                        };
+                        if let (Some(code), Some(text)) = (entry[0].as_string().map(code_value), text) {
+                            cmap.unicode.insert(code, text);
+                        }
+                    }
+                },
+                "endbfrange" => {
+                    for entry in operands.chunks_exact(3) {
+                        let (Some(low), Some(high)) = (entry[0].as_string().map(code_value), entry[1].as_string().map(code_value)) else {
+                            continue;
+                        };
+                        if high < low {
+                            continue;
+                        }
+                        match &entry[2] {
+                            Operand::String(start) => cmap.unicode_ranges.push(UnicodeRange {
+                                low,
+                                high,
+                                start: utf16_units(start),
+                            }),
+                            // One destination per code in the range
+                            Operand::Array(texts) => {
+                                for (code, text) in (low..=high).zip(texts) {
+                                    if let Some(text) = text.as_string() {
+                                        cmap.unicode.insert(code, String::from_utf16_lossy(&utf16_units(text)));
+                                    }
+                                }
+                            },
+                            _ => {},
+                        }
+                    }
+                },
+                "endcidchar" => {
+                    for entry in operands.chunks_exact(2) {
+                        if let (Some(code), Some(cid)) = (entry[0].as_string().map(code_value), entry[1].as_number()) {
+                            cmap.cids.insert(code, cid as u32);
+                        }
+                    }
+                },
+                "endcidrange" | "endnotdefrange" => {
+                    for entry in operands.chunks_exact(3) {
+                        let (Some(low), Some(high), Some(cid)) = (
+                            entry[0].as_string().map(code_value),
+                            entry[1].as_string().map(code_value),
+                            entry[2].as_number(),
+                        ) else {
+                            continue;
+                        };
+                        let range = CidRange { low, high, cid: cid as u32 };
+                        match keyword.as_str() {
+                            "endcidrange" => cmap.cid_ranges.push(range),
+                            _ => cmap.notdef_ranges.push(range),
+                        }
+                    }
+                },
+                "usecmap" => {
+                    cmap.use_cmap = operands.last().and_then(Operand::as_name).map(String::from);
+                },
+                "def" => {
+                    if let [.., Operand::Name(key), Operand::Number(mode)] = operands.as_slice() {
+                        if key == "WMode" {
+                            cmap.vertical = *mode == 1.0;
+                        }
+                    }
+                },
+                _ => {},
+            }
+            operands.clear();
+        }
+        cmap
+    }
+
+    /// Reads the code at the start of `bytes`, returning it with its length
+    /// in bytes. Bytes matching no codespace range are consumed as a code
+    /// of the shortest length whose ranges could start with them.
+    pub fn next_code(&self, bytes: &[u8]) -> (u32, usize) {
+        let matched = (1..=4.min(bytes.len()))
+            .find(|&length| self.codespace.iter().any(|range| range.contains(&bytes[..length])));
+        let length = matched
+            .or_else(|| self.codespace.iter()
+                .filter(|range| bytes.first().is_some_and(|b| (range.low[0]..=range.high[0]).contains(b)))
+                .map(|range| range.low.len())
+                .min())
+            .or_else(|| self.codespace.iter().map(|range| range.low.len()).min())
+            .unwrap_or(1)
+            .min(bytes.len());
+        (code_value(&bytes[..length]), length)
+    }
+
+    pub fn has_codespace(&self) -> bool {
+        !self.codespace.is_empty()
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self.vertical
+    }
+
+    /// Name of the predefined CMap, this one or one it extends, whose CIDs
+    /// are not available.
+    pub fn unavailable(&self) -> Option<&str> {
+        self.unavailable.as_deref().or_else(|| self.parent.as_ref()?.unavailable())
+    }
+
+    /// The CID selected by `code`, if the CMap maps it.
+    pub fn to_cid(&self, code: u32) -> Option<u32> {
+        if let Some(&cid) = self.cids.get(&code) {
+            return Some(cid);
+        }
+        let in_range = |range: &&CidRange| (range.low..=range.high).contains(&code);
+        if let Some(range) = self.cid_ranges.iter().rev().find(in_range) {
+            return Some(range.cid + (code - range.low));
+        }
+        if self.identity {
+            return Some(code);
+        }
+        self.parent.as_ref()
+            .and_then(|parent| parent.to_cid(code))
+            .or_else(|| self.notdef_ranges.iter().rev().find(in_range).map(|range| range.cid))
+    }
+
+    /// The text mapped to `code`; later ranges take precedence over earlier
+    /// ones.
+    pub fn to_unicode(&self, code: u32) -> Option<String> {
+        if let Some(text) = self.unicode.get(&code) {
+            return Some(text.clone());
+        }
+        if let Some(range) = self.unicode_ranges.iter().rev().find(|range| (range.low..=range.high).contains(&code)) {
+            let mut units = range.start.clone();
+            let last = units.last_mut()?;
+            *last = last.wrapping_add((code - range.low) as u16);
+            return Some(String::from_utf16_lossy(&units));
+        }
+        if self.unicode_codes {
+            // Four-byte codes are surrogate pairs
+            let units: Vec<u16> = match code {
+                0..=0xFFFF => vec![code as u16],
+                _ => vec![(code >> 16) as u16, code as u16],
+            };
+            return String::from_utf16(&units).ok();
+        }
+        self.parent.as_ref()?.to_unicode(code)
+    }
+}
+
+/// A code's bytes as a big-endian number.
+fn code_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |code, &b| code << 8 | u32::from(b))
+}
+
+/// Splits UTF-16BE text into units; a trailing odd byte is a unit of its
+/// own, as written by some producers for single-byte text.
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks(2)
+        .map(|pair| match pair {
+            [high, low] => u16::from_be_bytes([*high, *low]),
+            [byte] => u16::from(*byte),
+            _ => 0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    const CID_CMAP: &[u8] = b"
+        /CIDInit /ProcSet findresource begin
+        12 dict begin
+        begincmap
+        /WMode 1 def
+        2 begincodespacerange
+        <00> <80>
+        <8140> <9FFC>
+        endcodespacerange
+        1 begincidchar
+        <41> 34
+        endcidchar
+        1 begincidrange
+        <8140> <817E> 633
+        endcidrange
+        1 beginnotdefrange
+        <00> <1F> 1
+        endnotdefrange
+        endcmap
+        CMapName currentdict /CMap defineresource pop
+        end
+        end";
+
+    const UNICODE_CMAP: &[u8] = b"
+        1 begincodespacerange <0000> <FFFF> endcodespacerange
+        2 beginbfchar
+        <0003> <0020>
+        <0010> /f_i
+        endbfchar
+        2 beginbfrange
+        <0020> <0022> <0041>
+        <0030> <0031> [<00660066> <D83DDE00>]
+        endbfrange";
+
+    #[test]
+    fn codes_follow_the_codespace() {
+        let cmap = CMap::parse(CID_CMAP);
+        assert_eq!(cmap.next_code(b"A\x81\x40"), (0x41, 1));
+        assert_eq!(cmap.next_code(b"\x81\x40A"), (0x8140, 2));
+        // A lead byte of a two-byte range with a trail byte outside it
+        assert_eq!(cmap.next_code(b"\x81\x20"), (0x8120, 2));
+        // Bytes outside every range are read as the shortest code
+        assert_eq!(cmap.next_code(b"\xFF\xFF"), (0xFF, 1));
+        assert_eq!(cmap.next_code(b"\x81"), (0x81, 1));
+    }
+
+    #[test]
+    fn codes_map_to_cids() {
+        let cmap = CMap::parse(CID_CMAP);
+        assert!(cmap.is_vertical());
+        assert_eq!(cmap.to_cid(0x41), Some(34));
+        assert_eq!(cmap.to_cid(0x8142), Some(635));
+        assert_eq!(cmap.to_cid(0x05), Some(1));
+        assert_eq!(cmap.to_cid(0x42), None);
+    }
+
+    #[test]
+    fn codes_map_to_text() {
+        let cmap = CMap::parse(UNICODE_CMAP);
+        assert_eq!(cmap.to_unicode(0x03).as_deref(), Some(" "));
+        assert_eq!(cmap.to_unicode(0x10).as_deref(), Some("\u{FB01}"));
+        assert_eq!(cmap.to_unicode(0x21).as_deref(), Some("B"));
+        assert_eq!(cmap.to_unicode(0x30).as_deref(), Some("ff"));
+        assert_eq!(cmap.to_unicode(0x31).as_deref(), Some("\u{1F600}"));
+        assert_eq!(cmap.to_unicode(0x40), None);
+    }
+
+    #[test]
+    fn embedded_cmap_extends_predefined_one() {
+        let mut doc = Document::new();
+        let stream = Stream::new(dictionary! { "UseCMap" => "90ms-RKSJ-V" }, b"
+            1 begincidchar <8140> 633 endcidchar".to_vec());
+        let id = doc.add_object(stream);
+        let cmap = CMap::load(&doc, &Object::Reference(id)).unwrap();
+        assert_eq!(cmap.to_cid(0x8140), Some(633));
+        assert_eq!(cmap.next_code(b"\xA5"), (0xA5, 1));
+        assert!(cmap.is_vertical());
+        assert_eq!(cmap.unavailable(), Some("90ms-RKSJ-V"));
+    }
+
+    #[test]
+    fn predefined_cmaps() {
+        let identity = CMap::predefined("Identity-V");
+        assert!(identity.is_vertical());
+        assert_eq!(identity.unavailable(), None);
+        assert_eq!(identity.next_code(b"\x12\x34\x56"), (0x1234, 2));
+        assert_eq!(identity.to_cid(0x1234), Some(0x1234));
+
+        let unicode = CMap::predefined("UniGB-UTF16-H");
+        assert_eq!(unicode.next_code(b"\xD8\x3D\xDE\x00"), (0xD83D_DE00, 4));
+        assert_eq!(unicode.to_unicode(0xD83D_DE00).as_deref(), Some("\u{1F600}"));
+        assert_eq!(unicode.to_cid(0x4E00), None);
+        assert_eq!(unicode.unavailable(), Some("UniGB-UTF16-H"));
+
+        let gbk = CMap::predefined("GBK-EUC-H");
+        assert!(!gbk.is_vertical());
+        assert_eq!(gbk.next_code(b"a\xB0\xA1"), (0x61, 1));
+        assert_eq!(gbk.next_code(b"\xB0\xA1"), (0xB0A1, 2));
+
+        let big5 = CMap::predefined("ETen-B5-V");
+        assert!(big5.is_vertical());
+        assert_eq!(big5.next_code(b"\xA4\x40"), (0xA440, 2));
+    }
+}
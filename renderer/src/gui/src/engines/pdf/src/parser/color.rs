@@ -1,10 +1,21 @@
+use lopdf::{Document, Object};
+use super::resolve;
+use super::function::Function;
+
 #[derive(Debug, Clone)]
 pub enum ColorSpace {
     DeviceRGB,
     DeviceCMYK,
     DeviceGray,
+    Lab { white_point: [f32; 3], range: [f32; 4] },
     Indexed { base: Box<ColorSpace>, lookup: Vec<u8> },
-    Pattern,
+    /// `tint_transform` maps tints to `alternate`; it is `None` if the
+    /// function could not be read.
+    Separation { name: String, alternate: Box<ColorSpace>, tint_transform: Option<Function> },
+    DeviceN { names: Vec<String>, alternate: Box<ColorSpace>, tint_transform: Option<Function> },
+    /// `base` is given for uncolored tiling patterns, whose color is set
+    /// by the components preceding the pattern name in `scn`.
+    Pattern { base: Option<Box<ColorSpace>> },
 }
 
 #[derive(Debug, Clone)]
@@ -12,4 +23,279 @@ pub enum Color {
     RGB(f32, f32, f32),
     CMYK(f32, f32, f32, f32),
     Gray(f32),
-} 
\ No newline at end of file
+}
+
+impl Color {
+    /// Converts the color to RGB using the naive device conversions.
+    pub fn to_rgb(&self) -> [f32; 3] {
+        match *self {
+            Color::RGB(r, g, b) => [r, g, b],
+            Color::CMYK(c, m, y, k) => [
+                (1.0 - c) * (1.0 - k),
+                (1.0 - m) * (1.0 - k),
+                (1.0 - y) * (1.0 - k),
+            ],
+            Color::Gray(g) => [g, g, g],
+        }
+    }
+
+    /// The device color space a color set by `g`, `rg` or `k` belongs to.
+    pub fn device_space(&self) -> ColorSpace {
+        match self {
+            Color::RGB(..) => ColorSpace::DeviceRGB,
+            Color::CMYK(..) => ColorSpace::DeviceCMYK,
+            Color::Gray(_) => ColorSpace::DeviceGray,
+        }
+    }
+
+    pub fn to_rgba(&self, alpha: f32) -> [f32; 4] {
+        let [r, g, b] = self.to_rgb();
+        [r, g, b, alpha]
+    }
+}
+
+impl ColorSpace {
+    /// Resolves a color space given by family name, e.g. `/DeviceRGB` or the
+    /// inline image abbreviation `/RGB`.
+    pub fn from_name(name: &str) -> Option<ColorSpace> {
+        match name {
+            "DeviceRGB" | "RGB" | "CalRGB" => Some(ColorSpace::DeviceRGB),
+            "DeviceCMYK" | "CMYK" => Some(ColorSpace::DeviceCMYK),
+            "DeviceGray" | "G" | "CalGray" => Some(ColorSpace::DeviceGray),
+            "Pattern" => Some(ColorSpace::Pattern { base: None }),
+            _ => None,
+        }
+    }
+
+    /// Parses a color space object from a `/ColorSpace` resource or image
+    /// dictionary. Calibrated and ICC-based spaces are approximated by the
+    /// device space with the same number of components.
+    pub fn from_object(doc: &Document, object: &Object) -> Option<ColorSpace> {
+        let array = match resolve(doc, object) {
+            Object::Name(name) => return Self::from_name(&String::from_utf8_lossy(name)),
+            Object::Array(array) => array,
+            _ => return None,
+        };
+
+        let family = array.first()?.as_name_str().ok()?;
+        let operand = |index: usize| array.get(index).map(|o| resolve(doc, o));
+
+        match family {
+            "CalRGB" | "CalGray" | "CalCMYK" => Self::from_name(family),
+            "ICCBased" => {
+                let stream = operand(1)?.as_stream().ok()?;
+                if let Ok(alternate) = stream.dict.get(b"Alternate") {
+                    if let Some(space) = Self::from_object(doc, alternate) {
+                        return Some(space);
+                    }
+                }
+                match stream.dict.get(b"N").and_then(Object::as_i64).ok()? {
+                    1 => Some(ColorSpace::DeviceGray),
+                    4 => Some(ColorSpace::DeviceCMYK),
+                    _ => Some(ColorSpace::DeviceRGB),
+                }
+            },
+            "Lab" => {
+                let dict = operand(1).and_then(|o| o.as_dict().ok());
+                let numbers = |key: &[u8]| -> Vec<f32> {
+                    dict.and_then(|d| d.get(key).ok())
+                        .and_then(|o| o.as_array().ok())
+                        .map(|a| a.iter().filter_map(|n| n.as_float().ok()).collect())
+                        .unwrap_or_default()
+                };
+                let white = numbers(b"WhitePoint");
+                let range = numbers(b"Range");
+                Some(ColorSpace::Lab {
+                    white_point: match white[..] {
+                        [x, y, z] => [x, y, z],
+                        _ => [0.9505, 1.0, 1.089],
+                    },
+                    range: match range[..] {
+                        [a0, a1, b0, b1] => [a0, a1, b0, b1],
+                        _ => [-100.0, 100.0, -100.0, 100.0],
+                    },
+                })
+            },
+            "Indexed" | "I" => {
+                let base = Self::from_object(doc, operand(1)?)?;
+                let lookup = match operand(3)? {
+                    Object::String(bytes, _) => bytes.clone(),
+                    Object::Stream(stream) => stream.decompressed_content()
+                        .unwrap_or_else(|_| stream.content.clone()),
+                    _ => return None,
+                };
+                Some(ColorSpace::Indexed { base: Box::new(base), lookup })
+            },
+            "Separation" => {
+                let name = operand(1)?.as_name_str().ok()?.to_string();
+                let alternate = Self::from_object(doc, operand(2)?)?;
+                let tint_transform = operand(3).and_then(|o| Function::from_object(doc, o).ok());
+                Some(ColorSpace::Separation { name, alternate: Box::new(alternate), tint_transform })
+            },
+            "DeviceN" => {
+                let names = operand(1)?.as_array().ok()?
+                    .iter()
+                    .filter_map(|n| n.as_name_str().ok().map(str::to_string))
+                    .collect();
+                let alternate = Self::from_object(doc, operand(2)?)?;
+                let tint_transform = operand(3).and_then(|o| Function::from_object(doc, o).ok());
+                Some(ColorSpace::DeviceN { names, alternate: Box::new(alternate), tint_transform })
+            },
+            "Pattern" => {
+                let base = operand(1).and_then(|o| Self::from_object(doc, o));
+                Some(ColorSpace::Pattern { base: base.map(Box::new) })
+            },
+            _ => None,
+        }
+    }
+
+    pub fn components(&self) -> usize {
+        match self {
+            ColorSpace::DeviceRGB | ColorSpace::Lab { .. } => 3,
+            ColorSpace::DeviceCMYK => 4,
+            ColorSpace::DeviceGray
+            | ColorSpace::Indexed { .. }
+            | ColorSpace::Separation { .. } => 1,
+            ColorSpace::DeviceN { names, .. } => names.len(),
+            ColorSpace::Pattern { base } => base.as_ref().map_or(0, |base| base.components()),
+        }
+    }
+
+    /// The color selected when this space becomes current (`cs`/`CS`).
+    pub fn initial_color(&self) -> Color {
+        match self {
+            ColorSpace::DeviceCMYK => Color::CMYK(0.0, 0.0, 0.0, 1.0),
+            ColorSpace::Separation { .. } | ColorSpace::DeviceN { .. } => {
+                let tints = vec![1.0; self.components()];
+                self.color_from_components(&tints)
+            },
+            _ => {
+                let zeros = vec![0.0; self.components()];
+                self.color_from_components(&zeros)
+            },
+        }
+    }
+
+    /// Converts color operands given in this space to a device color.
+    pub fn color_from_components(&self, components: &[f32]) -> Color {
+        let value = |index: usize| components.get(index).copied().unwrap_or(0.0);
+
+        match self {
+            ColorSpace::DeviceRGB => Color::RGB(value(0), value(1), value(2)),
+            ColorSpace::DeviceCMYK => Color::CMYK(value(0), value(1), value(2), value(3)),
+            ColorSpace::DeviceGray | ColorSpace::Pattern { base: None } => Color::Gray(value(0)),
+            ColorSpace::Pattern { base: Some(base) } => base.color_from_components(components),
+            ColorSpace::Lab { white_point, range } => {
+                let a = value(1).max(range[0]).min(range[1]);
+                let b = value(2).max(range[2]).min(range[3]);
+                let [r, g, b] = lab_to_rgb(value(0), a, b, white_point);
+                Color::RGB(r, g, b)
+            },
+            ColorSpace::Indexed { base, lookup } => {
+                let count = base.components();
+                let start = value(0).max(0.0) as usize * count;
+                let entry: Vec<f32> = (start..start + count)
+                    .map(|i| lookup.get(i).copied().unwrap_or(0) as f32 / 255.0)
+                    .collect();
+                base.color_from_components(&entry)
+            },
+            ColorSpace::Separation { alternate, tint_transform: Some(function), .. }
+            | ColorSpace::DeviceN { alternate, tint_transform: Some(function), .. } => {
+                let tints: Vec<f32> = (0..self.components()).map(value).collect();
+                alternate.color_from_components(&function.evaluate(&tints))
+            },
+            // Without a tint transform, tints are approximated: process
+            // colorants map onto CMYK, anything else onto gray.
+            ColorSpace::Separation { name, .. } => match name.as_str() {
+                "Cyan" => Color::CMYK(value(0), 0.0, 0.0, 0.0),
+                "Magenta" => Color::CMYK(0.0, value(0), 0.0, 0.0),
+                "Yellow" => Color::CMYK(0.0, 0.0, value(0), 0.0),
+                _ => Color::Gray(1.0 - value(0)),
+            },
+            ColorSpace::DeviceN { names, .. } => {
+                let mut cmyk = [0.0; 4];
+                let mut other: f32 = 0.0;
+                for (index, name) in names.iter().enumerate() {
+                    match name.as_str() {
+                        "Cyan" => cmyk[0] = value(index),
+                        "Magenta" => cmyk[1] = value(index),
+                        "Yellow" => cmyk[2] = value(index),
+                        "Black" => cmyk[3] = value(index),
+                        _ => other = other.max(value(index)),
+                    }
+                }
+                cmyk[3] = cmyk[3].max(other);
+                Color::CMYK(cmyk[0], cmyk[1], cmyk[2], cmyk[3])
+            },
+        }
+    }
+}
+
+/// CIE L*a*b* to sRGB, ignoring chromatic adaptation of the white point.
+fn lab_to_rgb(l: f32, a: f32, b: f32, white_point: &[f32; 3]) -> [f32; 3] {
+    let inverse = |t: f32| {
+        if t > 6.0 / 29.0 {
+            t * t * t
+        } else {
+            108.0 / 841.0 * (t - 4.0 / 29.0)
+        }
+    };
+
+    let fy = (l + 16.0) / 116.0;
+    let x = white_point[0] * inverse(fy + a / 500.0);
+    let y = white_point[1] * inverse(fy);
+    let z = white_point[2] * inverse(fy - b / 200.0);
+
+    let linear = [
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ];
+    linear.map(|c| {
+        let c = c.clamp(0.0, 1.0);
+        if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    #[test]
+    fn separation_evaluates_its_tint_transform() {
+        let tint_transform = dictionary! {
+            "FunctionType" => 2,
+            "Domain" => vec![0.into(), 1.into()],
+            "C0" => vec![0.into(), 0.into(), 0.into()],
+            "C1" => vec![1.into(), 0.5.into(), 0.into()],
+            "N" => 1,
+        };
+        let object = Object::Array(vec![
+            "Separation".into(), "Spot".into(), "DeviceRGB".into(), Object::Dictionary(tint_transform),
+        ]);
+        let space = ColorSpace::from_object(&Document::new(), &object).unwrap();
+        assert_eq!(space.color_from_components(&[0.5]).to_rgb(), [0.5, 0.25, 0.0]);
+        assert_eq!(space.initial_color().to_rgb(), [1.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn separation_without_tint_transform_is_approximated() {
+        let object = Object::Array(vec!["Separation".into(), "Magenta".into(), "DeviceCMYK".into()]);
+        let space = ColorSpace::from_object(&Document::new(), &object).unwrap();
+        assert!(matches!(space.color_from_components(&[0.4]), Color::CMYK(c, m, _, _) if c == 0.0 && m == 0.4));
+    }
+
+    #[test]
+    fn indexed_looks_up_base_colors() {
+        let object = Object::Array(vec![
+            "Indexed".into(), "DeviceRGB".into(), 1.into(), Object::string_literal(vec![255, 0, 0, 0, 0, 255]),
+        ]);
+        let space = ColorSpace::from_object(&Document::new(), &object).unwrap();
+        assert_eq!(space.color_from_components(&[1.0]).to_rgb(), [0.0, 0.0, 1.0]);
+    }
+}
@@ -1,80 +1,213 @@
-use crate::content::{TextObject, VectorObject, PathCommand, Matrix};
+use crate::content::{
+    TextObject, Glyph, TextRenderMode, VectorObject, PathCommand, FillRule,
+    LineStyle, LineCap, LineJoin, RenderingIntent, BlendMode, SoftMask, ClipPath, Matrix,
+    ImageObject, MarkedContent, ShadingObject, Pattern, TilingPattern, PaintType, TilingType,
+    GlyphProcedure,
+};
 use crate::text::FontManager;
-use super::{PDFError, PDFResources};
-use super::color::Color;
-use lopdf::{Document, Object};
+use super::{PDFError, PDFResources, resolve};
+use super::FontType;
+use super::font::Type3Font;
+use super::diagnostics::{Diagnostic, DiagnosticKind, Diagnostics, Severity};
+use super::filters::{decode_stream, is_supported_filter, stream_filters};
+use super::image::{DecodedImage, ImageParams, inline_image_stream};
+use super::optional_content::OptionalContent;
+use super::shading::parse_shading;
+use super::color::{Color, ColorSpace};
+use super::lexer::{Lexer, Operand, Token};
+use lopdf::{Document, Dictionary, Object, ObjectId, Stream};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub enum Operator {
     BeginText,
     EndText,
     SetFont(String, f32),
-    ShowText(String),
-    ShowTextAdjusted(String),
+    ShowText(Vec<u8>),
+    ShowTextAdjusted(Vec<TextElement>),
     SetTextMatrix(f32, f32, f32, f32, f32, f32),
+    MoveTextPosition(f32, f32),
+    MoveTextPositionSetLeading(f32, f32),
+    NextLine,
+    NextLineShowText(Vec<u8>),
+    NextLineShowTextSpaced(f32, f32, Vec<u8>),
+    ConcatMatrix(f32, f32, f32, f32, f32, f32),
     SetCharSpacing(f32),
     SetWordSpacing(f32),
     SetHorizontalScaling(f32),
     SetLeading(f32),
+    SetTextRise(f32),
     SetRenderingMode(TextRenderMode),
     SetLineWidth(f32),
     SetLineCap(LineCap),
     SetLineJoin(LineJoin),
     SetMiterLimit(f32),
     SetDashPattern(Vec<f32>, f32),
+    SetRenderingIntent(RenderingIntent),
+    SetFlatness(f32),
     SetStrokeColor(Color),
     SetFillColor(Color),
+    SetStrokeColorSpace(String),
+    SetFillColorSpace(String),
+    SetStrokeColorComponents(Vec<f32>),
+    SetFillColorComponents(Vec<f32>),
+    SetStrokePattern(String, Vec<f32>),
+    SetFillPattern(String, Vec<f32>),
     MoveTo(f32, f32),
     LineTo(f32, f32),
     CurveTo(f32, f32, f32, f32, f32, f32),
+    CurveToInitial(f32, f32, f32, f32),
+    CurveToFinal(f32, f32, f32, f32),
+    Rectangle(f32, f32, f32, f32),
     ClosePath,
+    Stroke,
+    CloseAndStroke,
+    Fill(FillRule),
+    FillAndStroke(FillRule),
+    CloseFillAndStroke(FillRule),
+    EndPath,
+    Clip(FillRule),
     SaveState,
     RestoreState,
     SetGraphicsState(String),
+    DrawXObject(String),
+    PaintShading(String),
+    InlineImage(Stream),
+    BeginMarkedContent(String, Option<Operand>),
+    EndMarkedContent,
+    MarkPoint(String, Option<Operand>),
+    SetGlyphWidth(f32),
+    SetGlyphWidthAndBoundingBox(f32, [f32; 4]),
+    BeginCompatibility,
+    EndCompatibility,
 }
 
+/// An element of a `TJ` array: a string to show or a position adjustment
+/// in thousandths of text space.
 #[derive(Debug, Clone)]
-pub enum TextRenderMode {
-    Fill,
-    Stroke,
-    FillAndStroke,
-    Invisible,
-    FillAndClip,
-    StrokeAndClip,
-    FillStrokeAndClip,
-    Clip,
+pub enum TextElement {
+    Text(Vec<u8>),
+    Offset(f32),
 }
 
-#[derive(Debug, Clone)]
-pub enum LineCap {
-    Butt,
-    Round,
-    Square,
-}
+/// Operators defined by the PDF specification, used to tell unknown
+/// keywords from known operators with invalid operands.
+const OPERATORS: [&str; 73] = [
+    "b", "B", "b*", "B*", "BDC", "BI", "BMC", "BT", "BX", "c", "cm", "CS", "cs", "d", "d0",
+    "d1", "Do", "DP", "EI", "EMC", "ET", "EX", "f", "F", "f*", "G", "g", "gs", "h", "i",
+    "ID", "j", "J", "K", "k", "l", "m", "M", "MP", "n", "q", "Q", "re", "RG", "rg", "ri",
+    "s", "S", "SC", "sc", "SCN", "scn", "sh", "T*", "Tc", "Td", "TD", "Tf", "Tj", "TJ", "TL",
+    "Tm", "Tr", "Ts", "Tw", "Tz", "v", "w", "W", "W*", "y", "'", "\"",
+];
 
-#[derive(Debug, Clone)]
-pub enum LineJoin {
-    Miter,
-    Round,
-    Bevel,
+/// Nesting limit for form XObjects, guarding against reference cycles that
+/// go through distinct objects.
+const MAX_FORM_DEPTH: usize = 12;
+
+/// Objects produced by parsing a content stream.
+#[derive(Default)]
+pub struct ParsedContent {
+    pub text_objects: Vec<TextObject>,
+    pub vector_objects: Vec<VectorObject>,
+    pub image_objects: Vec<ImageObject>,
+    pub shading_objects: Vec<ShadingObject>,
+    pub clip_paths: Vec<ClipPath>,
+    pub marked_content: Vec<MarkedContent>,
+    pub patterns: Vec<Pattern>,
+    pub glyph_procedures: Vec<GlyphProcedure>,
+    pub diagnostics: Diagnostics,
 }
 
+/// Glyph width, as a fraction of the font size, assumed when the current
+/// font is unknown.
+const DEFAULT_GLYPH_WIDTH: f32 = 0.5;
+
+/// `TJ` adjustment, in thousandths of text space, beyond which the gap is
+/// treated as a word break in the extracted text.
+const WORD_BREAK_OFFSET: f32 = 200.0;
+
 pub struct ContentParser<'a> {
-    data: &'a [u8],
-    position: usize,
+    lexer: Lexer<'a>,
+    operands: Vec<Operand>,
     graphics_state_stack: Vec<GraphicsState>,
     current_state: GraphicsState,
+    current_path: Vec<PathCommand>,
+    current_point: (f32, f32),
+    subpath_start: (f32, f32),
+    in_text_block: bool,
+    // Text object state is not part of the graphics state, so `Q` inside a
+    // text object does not move the text position
+    text_matrix: Matrix,
+    text_line_matrix: Matrix,
+    // Fill rule of a pending `W`/`W*`, applied when the path is painted
+    pending_clip: Option<FillRule>,
+    output: ParsedContent,
+    // Form XObjects and tiling pattern cells currently being executed,
+    // outermost first
+    form_stack: Vec<ObjectId>,
+    marked_content_stack: Vec<MarkedContentScope>,
+    // Maps pattern space to the default user space: identity for a page,
+    // the form's initial CTM for a form XObject
+    pattern_space: Matrix,
+    // Pattern resources already decoded into `ParsedContent::patterns`
+    pattern_indices: HashMap<String, usize>,
+    // Type 3 glyph procedures already parsed into
+    // `ParsedContent::glyph_procedures`, by font resource name and code
+    glyph_procedure_indices: HashMap<(String, u32), usize>,
+    // Glyph metrics declared by `d0` or `d1` in a glyph procedure
+    glyph_width: f32,
+    glyph_bbox: Option<[f32; 4]>,
+    // Keyword and byte offset of the operator being executed
+    operator: String,
+    operator_offset: usize,
+    // Depth of `BX`/`EX` sections, inside which unknown operators are
+    // expected
+    compatibility_depth: usize,
     font_manager: &'a mut FontManager,
     resources: &'a PDFResources,
+    doc: &'a Document,
+    optional_content: &'a OptionalContent,
+}
+
+/// An open marked-content sequence.
+#[derive(Debug, Clone, Copy)]
+struct MarkedContentScope {
+    /// Index into `ParsedContent::marked_content`.
+    index: usize,
+    /// Set inside optional content that is currently hidden, including
+    /// sequences nested in a hidden one.
+    hidden: bool,
 }
 
+/// Parameters saved by `q` and restored by `Q`.
 #[derive(Debug, Clone)]
 struct GraphicsState {
     font: Option<String>,
     font_size: f32,
-    text_matrix: [f32; 6],
+    ctm: Matrix,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scaling: f32,
+    leading: f32,
+    rise: f32,
+    render_mode: TextRenderMode,
+    line_style: LineStyle,
+    rendering_intent: RenderingIntent,
+    flatness: f32,
+    stroke_color_space: ColorSpace,
+    fill_color_space: ColorSpace,
     stroke_color: Color,
     fill_color: Color,
+    /// Index into `ParsedContent::patterns` while the color space is
+    /// `/Pattern` and a pattern has been selected.
+    stroke_pattern: Option<usize>,
+    fill_pattern: Option<usize>,
+    stroke_alpha: f32,
+    fill_alpha: f32,
+    blend_mode: BlendMode,
+    soft_mask: Option<SoftMask>,
+    /// Index of the effective clip in `ParsedContent::clip_paths`.
+    clip: Option<usize>,
 }
 
 impl GraphicsState {
@@ -82,9 +215,27 @@ impl GraphicsState {
         GraphicsState {
             font: None,
             font_size: 12.0,
-            text_matrix: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
-            stroke_color: Color::RGB(0.0, 0.0, 0.0),
-            fill_color: Color::RGB(0.0, 0.0, 0.0),
+            ctm: Matrix::default(),
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scaling: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+            render_mode: TextRenderMode::Fill,
+            line_style: LineStyle::default(),
+            rendering_intent: RenderingIntent::RelativeColorimetric,
+            flatness: 1.0,
+            stroke_color_space: ColorSpace::DeviceGray,
+            fill_color_space: ColorSpace::DeviceGray,
+            stroke_color: Color::Gray(0.0),
+            fill_color: Color::Gray(0.0),
+            stroke_pattern: None,
+            fill_pattern: None,
+            stroke_alpha: 1.0,
+            fill_alpha: 1.0,
+            blend_mode: BlendMode::Normal,
+            soft_mask: None,
+            clip: None,
         }
     }
 }
@@ -93,407 +244,1324 @@ impl<'a> ContentParser<'a> {
     pub fn new(
         data: &'a [u8], 
         font_manager: &'a mut FontManager, 
-        resources: &'a PDFResources
+        resources: &'a PDFResources,
+        doc: &'a Document,
+        optional_content: &'a OptionalContent,
     ) -> Self {
         ContentParser {
-            data,
-            position: 0,
+            lexer: Lexer::new(data),
+            operands: Vec::new(),
             graphics_state_stack: Vec::new(),
             current_state: GraphicsState::new(),
+            current_path: Vec::new(),
+            current_point: (0.0, 0.0),
+            subpath_start: (0.0, 0.0),
+            in_text_block: false,
+            text_matrix: Matrix::default(),
+            text_line_matrix: Matrix::default(),
+            pending_clip: None,
+            output: ParsedContent::default(),
+            form_stack: Vec::new(),
+            marked_content_stack: Vec::new(),
+            pattern_space: Matrix::default(),
+            pattern_indices: HashMap::new(),
+            glyph_procedure_indices: HashMap::new(),
+            glyph_width: 0.0,
+            glyph_bbox: None,
+            operator: String::new(),
+            operator_offset: 0,
+            compatibility_depth: 0,
             font_manager,
             resources,
+            doc,
+            optional_content,
         }
     }
 
-    pub fn parse(&mut self) -> Option<(Vec<TextObject>, Vec<VectorObject>)> {
-        let mut text_objects = Vec::new();
-        let mut vector_objects = Vec::new();
-        let mut current_path = Vec::new();
-        let mut in_text_block = false;
-        let mut text_matrix = Matrix::default();
-
-        web_sys::console::log_1(&format!(
-            "Starting to parse content stream of {} bytes", 
-            self.data.len()
-        ).into());
-
-        // Print first few bytes for debugging
-        let preview: String = self.data.iter()
-            .take(50)
-            .map(|b| format!("{:02x}", b))
-            .collect::<Vec<_>>()
-            .join(" ");
-        web_sys::console::log_1(&format!("Content stream starts with: {}", preview).into());
-
-        while self.position < self.data.len() {
-            let current_pos = self.position;
-            match self.parse_next_operator() {
-                Some(op) => {
-                    web_sys::console::log_1(&format!(
-                        "Parsed operator at position {}: {:?}", 
-                        current_pos, op
-                    ).into());
-                    match op {
-                        Operator::BeginText => {
-                            in_text_block = true;
-                            text_matrix = Matrix::default();
-                        },
-                        Operator::EndText => {
-                            in_text_block = false;
-                        },
-                        Operator::SetFont(name, size) => {
-                            self.current_state.font = Some(name);
-                            self.current_state.font_size = size;
-                        },
-                        Operator::SetTextMatrix(a, b, c, d, e, f) => {
-                            if in_text_block {
-                                text_matrix = Matrix { a, b, c, d, e, f };
-                            }
-                        },
-                        Operator::ShowText(text) | Operator::ShowTextAdjusted(text) => {
-                            if !text.trim().is_empty() && in_text_block {
-                                text_objects.push(TextObject {
-                                    text: text.to_string(),
-                                    x: text_matrix.e,
-                                    y: text_matrix.f,
-                                    font_size: self.current_state.font_size * text_matrix.a.abs(),
-                                    font_name: self.current_state.font.clone().unwrap_or_else(|| "Default".to_string()),
-                                });
-                            }
-                        },
-                        Operator::MoveTo(x, y) => {
-                            current_path.push(PathCommand::MoveTo(x, y));
-                        },
-                        Operator::LineTo(x, y) => {
-                            current_path.push(PathCommand::LineTo(x, y));
-                        },
-                        Operator::CurveTo(x1, y1, x2, y2, x3, y3) => {
-                            current_path.push(PathCommand::CurveTo(x1, y1, x2, y2, x3, y3));
-                        },
-                        Operator::ClosePath => {
-                            current_path.push(PathCommand::Close);
-                            // Create vector object
-                            if !current_path.is_empty() {
-                                let stroke_color = match self.current_state.stroke_color {
-                                    Color::RGB(r, g, b) => [r, g, b, 1.0],
-                                    _ => [0.0, 0.0, 0.0, 1.0],
-                                };
-                                let fill_color = match self.current_state.fill_color {
-                                    Color::RGB(r, g, b) => Some([r, g, b, 1.0]),
-                                    _ => None,
-                                };
-                                vector_objects.push(VectorObject {
-                                    path_data: current_path.clone(),
-                                    stroke_color,
-                                    fill_color,
-                                });
-                                current_path.clear();
-                            }
-                        },
-                        _ => {} // Handle other operators
+    pub fn parse(&mut self) -> Option<ParsedContent> {
+        self.execute_stream();
+        Some(std::mem::take(&mut self.output))
+    }
+
+    /// Records a problem with the operator being executed.
+    fn diagnose(&mut self, kind: DiagnosticKind, severity: Severity, message: String) {
+        self.output.diagnostics.push(Diagnostic {
+            kind,
+            severity,
+            stream: self.form_stack.last().copied(),
+            offset: Some(self.operator_offset),
+            operator: Some(self.operator.clone()),
+            message,
+        });
+    }
+
+    /// Loads the resources of a form, pattern or glyph procedure, keeping
+    /// the problems found in them.
+    fn load_resources(&mut self, dict: &Dictionary) -> Option<PDFResources> {
+        let mut resources = PDFResources::new(self.doc, dict).ok()?;
+        self.output.diagnostics.extend(resources.take_diagnostics());
+        Some(resources)
+    }
+
+    fn execute_stream(&mut self) {
+        while let Some(op) = self.next_operator() {
+            match op {
+                Operator::BeginText => {
+                    self.in_text_block = true;
+                    self.text_matrix = Matrix::default();
+                    self.text_line_matrix = Matrix::default();
+                },
+                Operator::EndText => {
+                    self.in_text_block = false;
+                },
+                Operator::SetFont(name, size) => {
+                    self.current_state.font = Some(name);
+                    self.current_state.font_size = size;
+                },
+                Operator::SetCharSpacing(spacing) => {
+                    self.current_state.char_spacing = spacing;
+                },
+                Operator::SetWordSpacing(spacing) => {
+                    self.current_state.word_spacing = spacing;
+                },
+                Operator::SetHorizontalScaling(scale) => {
+                    self.current_state.horizontal_scaling = scale / 100.0;
+                },
+                Operator::SetLeading(leading) => {
+                    self.current_state.leading = leading;
+                },
+                Operator::SetTextRise(rise) => {
+                    self.current_state.rise = rise;
+                },
+                Operator::SetRenderingMode(mode) => {
+                    self.current_state.render_mode = mode;
+                },
+                Operator::SetTextMatrix(a, b, c, d, e, f) => {
+                    if self.in_text_block {
+                        let matrix = Matrix::new(a, b, c, d, e, f);
+                        self.text_matrix = matrix;
+                        self.text_line_matrix = matrix;
                     }
-                }
-                None => {
-                    // Log skipped bytes
-                    let skipped = self.data[current_pos]
-                        .to_string()
-                        .chars()
-                        .filter(|c| !c.is_control())
-                        .collect::<String>();
-                    web_sys::console::log_1(&format!(
-                        "Failed to parse operator at position {}, byte: {:02x} ({})", 
-                        current_pos, 
-                        self.data[current_pos],
-                        skipped
-                    ).into());
-                    self.advance();
-                }
+                },
+                Operator::MoveTextPosition(tx, ty) => self.move_text_position(tx, ty),
+                Operator::MoveTextPositionSetLeading(tx, ty) => {
+                    self.current_state.leading = -ty;
+                    self.move_text_position(tx, ty);
+                },
+                Operator::NextLine => self.next_line(),
+                Operator::ShowText(text) => self.show_text(&[TextElement::Text(text)]),
+                Operator::ShowTextAdjusted(elements) => self.show_text(&elements),
+                Operator::NextLineShowText(text) => {
+                    self.next_line();
+                    self.show_text(&[TextElement::Text(text)]);
+                },
+                Operator::NextLineShowTextSpaced(word_spacing, char_spacing, text) => {
+                    self.current_state.word_spacing = word_spacing;
+                    self.current_state.char_spacing = char_spacing;
+                    self.next_line();
+                    self.show_text(&[TextElement::Text(text)]);
+                },
+                Operator::SetLineWidth(width) => {
+                    self.current_state.line_style.width = width;
+                },
+                Operator::SetLineCap(cap) => {
+                    self.current_state.line_style.cap = cap;
+                },
+                Operator::SetLineJoin(join) => {
+                    self.current_state.line_style.join = join;
+                },
+                Operator::SetMiterLimit(limit) => {
+                    self.current_state.line_style.miter_limit = limit;
+                },
+                Operator::SetDashPattern(array, phase) => {
+                    self.current_state.line_style.dash_array = array;
+                    self.current_state.line_style.dash_phase = phase;
+                },
+                Operator::SetRenderingIntent(intent) => {
+                    self.current_state.rendering_intent = intent;
+                },
+                Operator::SetFlatness(flatness) => {
+                    self.current_state.flatness = flatness;
+                },
+                Operator::SetGraphicsState(name) => self.apply_ext_gstate(&name),
+                Operator::SetStrokeColor(color) => {
+                    self.current_state.stroke_color_space = color.device_space();
+                    self.current_state.stroke_color = color;
+                    self.current_state.stroke_pattern = None;
+                },
+                Operator::SetFillColor(color) => {
+                    self.current_state.fill_color_space = color.device_space();
+                    self.current_state.fill_color = color;
+                    self.current_state.fill_pattern = None;
+                },
+                Operator::SetStrokeColorSpace(name) => {
+                    if let Some(space) = self.lookup_color_space(&name) {
+                        self.current_state.stroke_color = space.initial_color();
+                        self.current_state.stroke_color_space = space;
+                        self.current_state.stroke_pattern = None;
+                    }
+                },
+                Operator::SetFillColorSpace(name) => {
+                    if let Some(space) = self.lookup_color_space(&name) {
+                        self.current_state.fill_color = space.initial_color();
+                        self.current_state.fill_color_space = space;
+                        self.current_state.fill_pattern = None;
+                    }
+                },
+                Operator::SetStrokeColorComponents(components) => {
+                    self.current_state.stroke_color = self.current_state.stroke_color_space
+                        .color_from_components(&components);
+                    self.current_state.stroke_pattern = None;
+                },
+                Operator::SetFillColorComponents(components) => {
+                    self.current_state.fill_color = self.current_state.fill_color_space
+                        .color_from_components(&components);
+                    self.current_state.fill_pattern = None;
+                },
+                // Components color the cell of an uncolored tiling pattern
+                Operator::SetStrokePattern(name, components) => {
+                    self.current_state.stroke_color = self.current_state.stroke_color_space
+                        .color_from_components(&components);
+                    self.current_state.stroke_pattern = self.pattern(&name);
+                },
+                Operator::SetFillPattern(name, components) => {
+                    self.current_state.fill_color = self.current_state.fill_color_space
+                        .color_from_components(&components);
+                    self.current_state.fill_pattern = self.pattern(&name);
+                },
+                Operator::DrawXObject(name) => self.draw_xobject(&name),
+                Operator::PaintShading(name) => self.paint_shading(&name),
+                Operator::InlineImage(stream) => self.draw_inline_image(&stream),
+                Operator::BeginMarkedContent(tag, properties) => self.begin_marked_content(tag, properties),
+                Operator::EndMarkedContent => self.end_marked_content(),
+                Operator::MarkPoint(tag, properties) => {
+                    let properties = properties.and_then(|p| self.property_list(&p));
+                    self.push_marked_content(tag, properties, true);
+                },
+                Operator::SetGlyphWidth(width) => {
+                    self.glyph_width = width;
+                    self.glyph_bbox = None;
+                },
+                Operator::SetGlyphWidthAndBoundingBox(width, bbox) => {
+                    self.glyph_width = width;
+                    self.glyph_bbox = Some(bbox);
+                },
+                Operator::BeginCompatibility => self.compatibility_depth += 1,
+                Operator::EndCompatibility => {
+                    self.compatibility_depth = self.compatibility_depth.saturating_sub(1);
+                },
+                Operator::SaveState => self.handle_save_state(),
+                Operator::RestoreState => self.handle_restore_state(),
+                Operator::ConcatMatrix(a, b, c, d, e, f) => {
+                    self.current_state.ctm = Matrix::new(a, b, c, d, e, f).multiply(&self.current_state.ctm);
+                },
+                // Path coordinates are stored in user space
+                Operator::MoveTo(x, y) => {
+                    let (x, y) = self.user_space(x, y);
+                    self.current_path.push(PathCommand::MoveTo(x, y));
+                    self.current_point = (x, y);
+                    self.subpath_start = (x, y);
+                },
+                Operator::LineTo(x, y) => {
+                    let (x, y) = self.user_space(x, y);
+                    self.current_path.push(PathCommand::LineTo(x, y));
+                    self.current_point = (x, y);
+                },
+                Operator::CurveTo(x1, y1, x2, y2, x3, y3) => {
+                    let (x1, y1) = self.user_space(x1, y1);
+                    let (x2, y2) = self.user_space(x2, y2);
+                    let (x3, y3) = self.user_space(x3, y3);
+                    self.current_path.push(PathCommand::CurveTo(x1, y1, x2, y2, x3, y3));
+                    self.current_point = (x3, y3);
+                },
+                Operator::CurveToInitial(x2, y2, x3, y3) => {
+                    let (x1, y1) = self.current_point;
+                    let (x2, y2) = self.user_space(x2, y2);
+                    let (x3, y3) = self.user_space(x3, y3);
+                    self.current_path.push(PathCommand::CurveTo(x1, y1, x2, y2, x3, y3));
+                    self.current_point = (x3, y3);
+                },
+                Operator::CurveToFinal(x1, y1, x3, y3) => {
+                    let (x1, y1) = self.user_space(x1, y1);
+                    let (x3, y3) = self.user_space(x3, y3);
+                    self.current_path.push(PathCommand::CurveTo(x1, y1, x3, y3, x3, y3));
+                    self.current_point = (x3, y3);
+                },
+                Operator::Rectangle(x, y, width, height) => {
+                    let (x0, y0) = self.user_space(x, y);
+                    let (x1, y1) = self.user_space(x + width, y);
+                    let (x2, y2) = self.user_space(x + width, y + height);
+                    let (x3, y3) = self.user_space(x, y + height);
+                    self.current_path.extend_from_slice(&[
+                        PathCommand::MoveTo(x0, y0),
+                        PathCommand::LineTo(x1, y1),
+                        PathCommand::LineTo(x2, y2),
+                        PathCommand::LineTo(x3, y3),
+                        PathCommand::Close,
+                    ]);
+                    self.current_point = (x0, y0);
+                    self.subpath_start = (x0, y0);
+                },
+                Operator::ClosePath => self.close_path(),
+                Operator::Stroke => self.paint_path(true, None),
+                Operator::CloseAndStroke => {
+                    self.close_path();
+                    self.paint_path(true, None);
+                },
+                Operator::Fill(rule) => self.paint_path(false, Some(rule)),
+                Operator::FillAndStroke(rule) => self.paint_path(true, Some(rule)),
+                Operator::CloseFillAndStroke(rule) => {
+                    self.close_path();
+                    self.paint_path(true, Some(rule));
+                },
+                Operator::EndPath => self.end_path(),
+                Operator::Clip(rule) => self.pending_clip = Some(rule),
             }
         }
+    }
+
+    fn move_text_position(&mut self, tx: f32, ty: f32) {
+        let matrix = Matrix::translate(tx, ty).multiply(&self.text_line_matrix);
+        self.text_matrix = matrix;
+        self.text_line_matrix = matrix;
+    }
+
+    fn next_line(&mut self) {
+        let leading = self.current_state.leading;
+        self.move_text_position(0.0, -leading);
+    }
+
+    /// Emits a text object at the current text position and advances the
+    /// text matrix past the shown glyphs and any `TJ` adjustments.
+    fn show_text(&mut self, elements: &[TextElement]) {
+        if !self.in_text_block {
+            return;
+        }
 
-        web_sys::console::log_1(&format!(
-            "Finished parsing content stream. Found {} text objects and {} vector objects",
-            text_objects.len(),
-            vector_objects.len()
-        ).into());
-
-        Some((text_objects, vector_objects))
-    }
-
-    fn parse_next_operator(&mut self) -> Option<Operator> {
-        self.skip_whitespace();
-        
-        // Log the current position and next few bytes for debugging
-        let preview: String = self.data[self.position..]
-            .iter()
-            .take(10)
-            .map(|b| format!("{:02x}", b))
-            .collect::<Vec<_>>()
-            .join(" ");
-        web_sys::console::log_1(&format!(
-            "Parsing at position {}, next bytes: {}", 
-            self.position, 
-            preview
-        ).into());
-
-        match self.peek_byte()? {
-            b'q' => {
-                self.advance();
-                Some(Operator::SaveState)
-            },
-            b'Q' => {
-                self.advance();
-                Some(Operator::RestoreState)
-            },
-            b'B' => self.parse_begin_text(),
-            b'E' => self.parse_end_text(),
-            b'T' => self.parse_text_operator(),
-            b'(' => {
-                if let Some(text) = self.parse_text_string() {
-                    Some(Operator::ShowText(text))
-                } else {
-                    None
-                }
-            },
-            b'[' => {
-                if let Some(text) = self.parse_text_array() {
-                    Some(Operator::ShowTextAdjusted(text))
-                } else {
-                    None
-                }
-            },
-            b'/' => {
-                // Font selection
-                if let Some(name) = self.parse_name() {
-                    self.skip_whitespace();
-                    if let Some(size) = self.parse_number() {
-                        return Some(Operator::SetFont(name, size));
+        // Text space to user space
+        let start = self.text_matrix.multiply(&self.current_state.ctm);
+        let (x, y) = start.transform_point(0.0, self.current_state.rise);
+        let resources = self.resources;
+        let font = self.current_state.font.as_deref()
+            .and_then(|name| resources.get_font(name).ok().flatten());
+        let mut text = String::new();
+        let mut glyphs = Vec::new();
+
+        for element in elements {
+            match element {
+                TextElement::Text(bytes) => {
+                    let mut remaining = bytes.as_slice();
+                    while !remaining.is_empty() {
+                        let (code, length) = match font {
+                            Some(font) => font.next_code(remaining),
+                            None => (u32::from(remaining[0]), 1),
+                        };
+                        remaining = &remaining[length..];
+                        let glyph = self.show_glyph(code, length);
+                        text.push_str(&glyph.text);
+                        glyphs.push(glyph);
                     }
-                }
-                None
-            },
-            b'm' => {
-                self.advance();
-                let x = self.parse_number()?;
-                let y = self.parse_number()?;
-                Some(Operator::MoveTo(x, y))
-            },
-            b'l' => {
-                self.advance();
-                let x = self.parse_number()?;
-                let y = self.parse_number()?;
-                Some(Operator::LineTo(x, y))
-            },
-            byte => {
-                // Skip unknown operators but log them
-                let mut operator = String::new();
-                while let Some(b) = self.peek_byte() {
-                    if b.is_ascii_whitespace() {
-                        break;
+                },
+                TextElement::Offset(amount) => {
+                    // Large gaps in kerned text are word breaks
+                    if *amount <= -WORD_BREAK_OFFSET && !text.is_empty() && !text.ends_with(' ') {
+                        text.push(' ');
                     }
-                    operator.push(b as char);
-                    self.advance();
-                }
-                web_sys::console::log_1(&format!(
-                    "Unknown operator '{}' at position {}, byte: {:02x} ({})",
-                    operator,
-                    self.position - operator.len(),
-                    byte,
-                    byte as char
-                ).into());
-                None
+                    // Vertical text moves down instead, without horizontal scaling
+                    let offset = -amount / 1000.0 * self.current_state.font_size;
+                    let translation = if font.is_some_and(|font| font.is_vertical()) {
+                        Matrix::translate(0.0, offset)
+                    } else {
+                        Matrix::translate(offset * self.current_state.horizontal_scaling, 0.0)
+                    };
+                    self.text_matrix = translation.multiply(&self.text_matrix);
+                },
             }
         }
-    }
 
-    fn parse_begin_text(&mut self) -> Option<Operator> {
-        self.advance(); // Skip 'B'
-        if self.peek_byte()? == b'T' {
-            self.advance();
-            Some(Operator::BeginText)
-        } else {
-            None
+        // Glyphs without text, such as most Type 3 glyphs and spaces, are
+        // still painted; only extracted text depends on `text`
+        if !glyphs.is_empty() && !self.content_hidden() {
+            self.output.text_objects.push(TextObject {
+                text,
+                x,
+                y,
+                font_size: self.current_state.font_size * start.vertical_scale(),
+                font_name: self.current_state.font.clone().unwrap_or_else(|| "Default".to_string()),
+                glyphs,
+                fill_color: self.current_state.fill_color.to_rgba(self.current_state.fill_alpha),
+                stroke_color: self.current_state.stroke_color.to_rgba(self.current_state.stroke_alpha),
+                fill_pattern: self.current_state.fill_pattern,
+                stroke_pattern: self.current_state.stroke_pattern,
+                render_mode: self.current_state.render_mode,
+                horizontal_scaling: self.current_state.horizontal_scaling,
+                rise: self.current_state.rise,
+                blend_mode: self.current_state.blend_mode,
+                soft_mask: self.current_state.soft_mask.clone(),
+                clip: self.current_state.clip,
+                marked_content: self.marked_content_stack.last().map(|scope| scope.index),
+            });
         }
     }
 
-    fn parse_end_text(&mut self) -> Option<Operator> {
-        self.advance(); // Skip 'E'
-        if self.peek_byte()? == b'T' {
-            self.advance();
-            Some(Operator::EndText)
+    /// Positions a single glyph at the current text position and advances
+    /// the text matrix by its width. `length` is the code's size in bytes.
+    fn show_glyph(&mut self, code: u32, length: usize) -> Glyph {
+        let resources = self.resources;
+        let state = &self.current_state;
+        let font = state.font.as_deref()
+            .and_then(|name| resources.get_font(name).ok().flatten());
+
+        let width = font.map_or(DEFAULT_GLYPH_WIDTH, |font| font.glyph_width(code));
+        // Word spacing applies to the single-byte code 32 only
+        let spacing = if code == 32 && length == 1 {
+            state.char_spacing + state.word_spacing
         } else {
-            None
+            state.char_spacing
+        };
+
+        let matrix = self.text_matrix.multiply(&state.ctm);
+        // Vertical glyphs are placed by their vertical origin, displaced
+        // from the horizontal one, and advance downwards
+        let vertical = font.and_then(|font| font.vertical_metrics(code));
+        let (x, y, advance, translation) = match vertical {
+            Some([w1y, vx, vy]) => {
+                let ty = w1y * state.font_size + spacing;
+                let (x, y) = matrix.transform_point(-vx * state.font_size, state.rise - vy * state.font_size);
+                (x, y, ty.abs() * (matrix.c * matrix.c + matrix.d * matrix.d).sqrt(), Matrix::translate(0.0, ty))
+            },
+            None => {
+                let tx = (width * state.font_size + spacing) * state.horizontal_scaling;
+                let (x, y) = matrix.transform_point(0.0, state.rise);
+                (x, y, tx * (matrix.a * matrix.a + matrix.b * matrix.b).sqrt(), Matrix::translate(tx, 0.0))
+            },
+        };
+
+        // Type 3 glyph space maps to text space through the font matrix
+        let type3 = font.and_then(|font| Some((font.type3()?, font.glyph_name(code)?))).map(|(type3, glyph_name)| {
+            let rendering = Matrix::new(
+                state.font_size * state.horizontal_scaling, 0.0,
+                0.0, state.font_size,
+                0.0, state.rise,
+            ).multiply(&matrix);
+            (type3, glyph_name, type3.font_matrix.multiply(&rendering), state.font.clone().unwrap_or_default())
+        });
+        // Codes whose glyph has no known name fall back to Latin-1; CIDs
+        // bear no relation to Unicode
+        let text = font.and_then(|font| font.to_unicode(code))
+            .or_else(|| match font.map(|font| font.font_type) {
+                Some(FontType::Type0) => Some(char::REPLACEMENT_CHARACTER.to_string()),
+                _ => char::from_u32(code).map(String::from),
+            })
+            .unwrap_or_default();
+        let procedure = type3.and_then(|(type3, glyph_name, glyph_matrix, font_name)| {
+            Some((self.glyph_procedure(&font_name, code, glyph_name, type3)?, glyph_matrix))
+        });
+
+        let glyph = Glyph {
+            code,
+            text,
+            x,
+            y,
+            advance,
+            glyph_id: font.and_then(|font| font.glyph_id(code)),
+            procedure,
+        };
+
+        self.text_matrix = translation.multiply(&self.text_matrix);
+        glyph
+    }
+
+    /// Parses the glyph procedure drawing `code`, named `glyph_name` by the
+    /// font's encoding, in a Type 3 font the first time it is shown and
+    /// returns its index.
+    fn glyph_procedure(&mut self, font_name: &str, code: u32, glyph_name: &str, type3: &Type3Font) -> Option<usize> {
+        let key = (font_name.to_string(), code);
+        if let Some(&index) = self.glyph_procedure_indices.get(&key) {
+            return Some(index);
         }
+        let id = type3.char_proc(glyph_name)?;
+        let stream = self.doc.get_object(id).ok()?.as_stream().ok()?;
+
+        if self.form_stack.contains(&id) || self.form_stack.len() >= MAX_FORM_DEPTH {
+            self.diagnose(DiagnosticKind::RecursionLimit, Severity::Error, format!(
+                "Skipping recursive glyph procedure {:?} at depth {}", id, self.form_stack.len()
+            ));
+            return None;
+        }
+        let content = match decode_stream(stream) {
+            Ok(content) => content,
+            Err(e) => {
+                self.diagnose(DiagnosticKind::DecodeFailed, Severity::Error, format!(
+                    "Failed to decode glyph procedure for code {}: {:?}", code, e
+                ));
+                return None;
+            }
+        };
+
+        let font_resources = type3.resources.as_ref()
+            .and_then(|dict| self.load_resources(dict));
+        let resources = font_resources.as_ref().unwrap_or(self.resources);
+
+        let mut glyph_parser = ContentParser::new(
+            &content,
+            &mut *self.font_manager,
+            resources,
+            self.doc,
+            self.optional_content,
+        );
+        glyph_parser.form_stack = self.form_stack.clone();
+        glyph_parser.form_stack.push(id);
+        glyph_parser.execute_stream();
+        let mut glyph_content = std::mem::take(&mut glyph_parser.output);
+        self.output.diagnostics.extend(std::mem::take(&mut glyph_content.diagnostics));
+
+        self.output.glyph_procedures.push(GlyphProcedure {
+            font_name: key.0.clone(),
+            code,
+            width: glyph_parser.glyph_width,
+            bbox: glyph_parser.glyph_bbox,
+            content: glyph_content,
+        });
+        let index = self.output.glyph_procedures.len() - 1;
+        self.glyph_procedure_indices.insert(key, index);
+        Some(index)
     }
 
-    fn parse_text_operator(&mut self) -> Option<Operator> {
-        self.advance(); // Skip 'T'
-        match self.peek_byte()? {
-            b'm' => self.parse_text_matrix(),
-            b'f' => {
-                self.advance();
-                let name = self.parse_name()?;
-                let size = self.parse_number()?;
-                Some(Operator::SetFont(name, size))
+    fn draw_xobject(&mut self, name: &str) {
+        let stream = self.resources.get_xobject(name)
+            .and_then(|id| Some((id, self.doc.get_object(id).ok()?.as_stream().ok()?)));
+        let Some((id, stream)) = stream else {
+            self.diagnose(DiagnosticKind::MissingResource, Severity::Error, format!("Unknown XObject '{}'", name));
+            return;
+        };
+
+        if let Ok(oc) = stream.dict.get(b"OC") {
+            if !self.optional_content.is_visible(self.doc, oc) {
+                return;
+            }
+        }
+
+        match stream.dict.get(b"Subtype").and_then(Object::as_name_str) {
+            Ok("Form") => self.draw_form(id, stream),
+            Ok("Image") => self.draw_image(stream),
+            subtype => {
+                let subtype = subtype.ok().map(str::to_string);
+                self.diagnose(DiagnosticKind::Unsupported, Severity::Warning, format!(
+                    "Skipping XObject '{}' with subtype {:?}", name, subtype
+                ));
             },
-            _ => None,
         }
     }
 
-    fn parse_text_matrix(&mut self) -> Option<Operator> {
-        self.advance(); // Skip 'm'
-        self.skip_whitespace();
-        
-        let a = self.parse_number()?;
-        let b = self.parse_number()?;
-        let c = self.parse_number()?;
-        let d = self.parse_number()?;
-        let e = self.parse_number()?;
-        let f = self.parse_number()?;
-        
-        Some(Operator::SetTextMatrix(a, b, c, d, e, f))
+    /// Decodes and paints an image; hidden images are not decoded.
+    fn draw_image(&mut self, stream: &Stream) {
+        if self.content_hidden() || !self.has_supported_filters(stream) {
+            return;
+        }
+        let fill_color = self.current_state.fill_color.to_rgba(self.current_state.fill_alpha);
+        match DecodedImage::from_stream(self.doc, stream, fill_color) {
+            Ok(image) => self.paint_image(image),
+            Err(e) => self.diagnose(DiagnosticKind::DecodeFailed, Severity::Error, format!(
+                "Failed to decode image XObject: {:?}", e
+            )),
+        }
     }
 
-    fn parse_text_string(&mut self) -> Option<String> {
-        let mut text = String::new();
-        if self.peek_byte()? != b'(' {
-            return None;
+    fn draw_inline_image(&mut self, stream: &Stream) {
+        if self.content_hidden() || !self.has_supported_filters(stream) {
+            return;
         }
-        self.advance(); // Skip '('
-        
-        let mut escaped = false;
-        while let Some(b) = self.peek_byte() {
-            self.advance();
-            match b {
-                b')' if !escaped => break,
-                b'\\' if !escaped => escaped = true,
-                _ => {
-                    if escaped {
-                        match b {
-                            b'n' => text.push('\n'),
-                            b'r' => text.push('\r'),
-                            b't' => text.push('\t'),
-                            b'(' => text.push('('),
-                            b')' => text.push(')'),
-                            b'\\' => text.push('\\'),
-                            _ => text.push(b as char),
-                        }
-                        escaped = false;
-                    } else {
-                        text.push(b as char);
-                    }
-                }
+        let fill_color = self.current_state.fill_color.to_rgba(self.current_state.fill_alpha);
+        let image = self.inline_image_params(&stream.dict)
+            .ok_or_else(|| PDFError::ParseError("Invalid inline image dictionary".to_string()))
+            .and_then(|params| DecodedImage::from_params(self.doc, stream, params, fill_color));
+        match image {
+            Ok(image) => self.paint_image(image),
+            Err(e) => self.diagnose(DiagnosticKind::DecodeFailed, Severity::Error, format!(
+                "Failed to decode inline image: {:?}", e
+            )),
+        }
+    }
+
+    /// Reports an image whose data is encoded with a filter that cannot be
+    /// decoded, such as `CCITTFaxDecode`.
+    fn has_supported_filters(&mut self, stream: &Stream) -> bool {
+        match stream_filters(stream).into_iter().find(|filter| !is_supported_filter(&filter.name)) {
+            Some(filter) => {
+                self.diagnose(DiagnosticKind::Unsupported, Severity::Error, format!(
+                    "Skipping image with unsupported filter {}", filter.name
+                ));
+                false
+            },
+            None => true,
+        }
+    }
+
+    /// Inline images may name a color space from the `/ColorSpace` resource.
+    fn inline_image_params(&self, dict: &Dictionary) -> Option<ImageParams> {
+        let mut params = ImageParams::from_dictionary(self.doc, dict).ok()?;
+        if let (Some(_), Ok(name)) = (&params.color_space, dict.get(b"ColorSpace").and_then(Object::as_name_str)) {
+            if let Some(space) = self.resources.get_color_space(name) {
+                params.color_space = Some(space);
             }
         }
-        Some(text)
+        Some(params)
     }
 
-    fn parse_text_array(&mut self) -> Option<String> {
-        self.advance(); // Skip '['
-        let mut text = String::new();
-        
-        while let Some(b) = self.peek_byte() {
-            match b {
-                b']' => {
-                    self.advance();
-                    break;
-                },
-                b'(' => {
-                    if let Some(str_part) = self.parse_text_string() {
-                        text.push_str(&str_part);
-                    }
-                },
-                _ => {
-                    self.advance();
-                }
+    /// Places an image on the unit square of the current user space.
+    fn paint_image(&mut self, image: DecodedImage) {
+        let DecodedImage { params, data, mask, mask_error } = image;
+        if let Some(e) = mask_error {
+            self.diagnose(DiagnosticKind::DecodeFailed, Severity::Warning, format!(
+                "Drawing image without its mask: {:?}", e
+            ));
+        }
+        self.output.image_objects.push(ImageObject {
+            width: params.width,
+            height: params.height,
+            color_space: params.color_space,
+            bits_per_component: params.bits_per_component,
+            data,
+            matrix: self.current_state.ctm,
+            mask,
+            interpolate: params.interpolate,
+            alpha: self.current_state.fill_alpha,
+            blend_mode: self.current_state.blend_mode,
+            soft_mask: self.current_state.soft_mask.clone(),
+            clip: self.current_state.clip,
+            marked_content: self.marked_content_stack.last().map(|scope| scope.index),
+        });
+    }
+
+    /// Executes a form XObject's content stream in a nested parser that
+    /// shares this parser's output.
+    fn draw_form(&mut self, id: ObjectId, stream: &Stream) {
+        if self.form_stack.contains(&id) || self.form_stack.len() >= MAX_FORM_DEPTH {
+            self.diagnose(DiagnosticKind::RecursionLimit, Severity::Error, format!(
+                "Skipping recursive form XObject {:?} at depth {}", id, self.form_stack.len()
+            ));
+            return;
+        }
+
+        let content = match decode_stream(stream) {
+            Ok(content) => content,
+            Err(e) => {
+                self.diagnose(DiagnosticKind::DecodeFailed, Severity::Error, format!(
+                    "Failed to decode form XObject {:?}: {:?}", id, e
+                ));
+                return;
             }
+        };
+
+        let matrix = self.matrix_entry(&stream.dict);
+        let bbox: Vec<f32> = stream.dict.get(b"BBox").ok()
+            .and_then(|o| resolve(self.doc, o).as_array().ok())
+            .map(|a| a.iter().filter_map(|n| n.as_float().ok()).collect())
+            .unwrap_or_default();
+
+        // Forms without their own resources inherit those of the caller
+        let form_resources = stream.dict.get(b"Resources").ok()
+            .and_then(|o| resolve(self.doc, o).as_dict().ok())
+            .and_then(|dict| self.load_resources(dict));
+        let resources = form_resources.as_ref().unwrap_or(self.resources);
+
+        self.handle_save_state();
+        self.current_state.ctm = matrix.multiply(&self.current_state.ctm);
+
+        // The bounding box acts as an implicit clip
+        if let [x0, y0, x1, y1] = bbox[..] {
+            let saved_path = std::mem::take(&mut self.current_path);
+            let corners = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+            for (index, (x, y)) in corners.into_iter().enumerate() {
+                let (x, y) = self.user_space(x, y);
+                self.current_path.push(if index == 0 {
+                    PathCommand::MoveTo(x, y)
+                } else {
+                    PathCommand::LineTo(x, y)
+                });
+            }
+            self.current_path.push(PathCommand::Close);
+            self.pending_clip = Some(FillRule::NonZero);
+            self.end_path();
+            self.current_path = saved_path;
+        }
+
+        let mut form_parser = ContentParser::new(
+            &content,
+            &mut *self.font_manager,
+            resources,
+            self.doc,
+            self.optional_content,
+        );
+        form_parser.current_state = self.current_state.clone();
+        form_parser.pattern_space = self.current_state.ctm;
+        form_parser.marked_content_stack = self.marked_content_stack.clone();
+        form_parser.form_stack = self.form_stack.clone();
+        form_parser.form_stack.push(id);
+        form_parser.output = std::mem::take(&mut self.output);
+        form_parser.execute_stream();
+        self.output = std::mem::take(&mut form_parser.output);
+
+        self.handle_restore_state();
+    }
+
+    /// Reads a `/Matrix` entry, defaulting to the identity.
+    fn matrix_entry(&self, dict: &Dictionary) -> Matrix {
+        let numbers: Vec<f32> = dict.get(b"Matrix").ok()
+            .and_then(|o| resolve(self.doc, o).as_array().ok())
+            .map(|a| a.iter().filter_map(|n| n.as_float().ok()).collect())
+            .unwrap_or_default();
+        match numbers[..] {
+            [a, b, c, d, e, f] => Matrix::new(a, b, c, d, e, f),
+            _ => Matrix::default(),
         }
-        
-        Some(text)
     }
 
-    fn parse_name(&mut self) -> Option<String> {
-        if self.peek_byte()? != b'/' {
+    /// Paints a shading resource over the current clip. `sh` ignores the
+    /// shading's `/Background`.
+    fn paint_shading(&mut self, name: &str) {
+        if self.content_hidden() {
+            return;
+        }
+        let Some(object) = self.resources.get_shading(name) else {
+            self.diagnose(DiagnosticKind::MissingResource, Severity::Error, format!("Unknown shading '{}'", name));
+            return;
+        };
+
+        match parse_shading(self.doc, object, self.current_state.ctm) {
+            Ok(mut shading) => {
+                shading.background = None;
+                self.output.shading_objects.push(ShadingObject {
+                    shading,
+                    alpha: self.current_state.fill_alpha,
+                    blend_mode: self.current_state.blend_mode,
+                    soft_mask: self.current_state.soft_mask.clone(),
+                    clip: self.current_state.clip,
+                    marked_content: self.marked_content_stack.last().map(|scope| scope.index),
+                });
+            },
+            Err(e) => self.diagnose(DiagnosticKind::DecodeFailed, Severity::Error, format!(
+                "Failed to decode shading '{}': {:?}", name, e
+            )),
+        }
+    }
+
+    /// Decodes a pattern resource into `ParsedContent::patterns` the first
+    /// time it is selected and returns its index.
+    fn pattern(&mut self, name: &str) -> Option<usize> {
+        if let Some(&index) = self.pattern_indices.get(name) {
+            return Some(index);
+        }
+        let Some(object) = self.resources.get_pattern(name) else {
+            self.diagnose(DiagnosticKind::MissingResource, Severity::Error, format!("Unknown pattern '{}'", name));
             return None;
+        };
+
+        match self.decode_pattern(object) {
+            Ok(pattern) => {
+                self.output.patterns.push(pattern);
+                let index = self.output.patterns.len() - 1;
+                self.pattern_indices.insert(name.to_string(), index);
+                Some(index)
+            },
+            Err(e) => {
+                self.diagnose(DiagnosticKind::DecodeFailed, Severity::Error, format!(
+                    "Failed to decode pattern '{}': {:?}", name, e
+                ));
+                None
+            }
+        }
+    }
+
+    fn decode_pattern(&mut self, object: &Object) -> Result<Pattern, PDFError> {
+        let doc = self.doc;
+        let (dict, stream) = match resolve(doc, object) {
+            Object::Dictionary(dict) => (dict, None),
+            Object::Stream(stream) => (&stream.dict, Some(stream)),
+            _ => return Err(PDFError::ParseError("Pattern is not a dictionary".to_string())),
+        };
+        // The pattern matrix maps pattern space into the default space of
+        // the content stream that uses it, not the current user space
+        let matrix = self.matrix_entry(dict).multiply(&self.pattern_space);
+
+        match dict.get(b"PatternType").and_then(Object::as_i64) {
+            Ok(1) => {
+                let stream = stream
+                    .ok_or_else(|| PDFError::ParseError("Tiling pattern is not a stream".to_string()))?;
+                self.tiling_pattern(object.as_reference().ok(), stream, matrix).map(Pattern::Tiling)
+            },
+            Ok(2) => {
+                let shading = dict.get(b"Shading")
+                    .map_err(|_| PDFError::ParseError("Shading pattern without /Shading".to_string()))?;
+                Ok(Pattern::Shading(parse_shading(self.doc, shading, matrix)?))
+            },
+            other => Err(PDFError::ParseError(format!("Unsupported pattern type {:?}", other.ok()))),
+        }
+    }
+
+    /// Parses the cell of a tiling pattern as its own content stream, with
+    /// pattern space as its user space.
+    fn tiling_pattern(&mut self, id: Option<ObjectId>, stream: &Stream, matrix: Matrix) -> Result<TilingPattern, PDFError> {
+        let dict = &stream.dict;
+        let integer = |key: &[u8]| dict.get(key).and_then(Object::as_i64).unwrap_or(0) as i32;
+        let paint_type = PaintType::from_code(integer(b"PaintType"))
+            .ok_or_else(|| PDFError::ParseError("Tiling pattern with invalid PaintType".to_string()))?;
+        let tiling_type = TilingType::from_code(integer(b"TilingType"))
+            .unwrap_or(TilingType::ConstantSpacing);
+        let number = |key: &[u8]| dict.get(key).ok()
+            .and_then(|o| resolve(self.doc, o).as_float().ok());
+        let (Some(x_step), Some(y_step)) = (number(b"XStep"), number(b"YStep")) else {
+            return Err(PDFError::ParseError("Tiling pattern without XStep or YStep".to_string()));
+        };
+        let bbox: Vec<f32> = dict.get(b"BBox").ok()
+            .and_then(|o| resolve(self.doc, o).as_array().ok())
+            .map(|a| a.iter().filter_map(|n| n.as_float().ok()).collect())
+            .unwrap_or_default();
+        let bbox = match bbox[..] {
+            [x0, y0, x1, y1] => [x0, y0, x1, y1],
+            _ => return Err(PDFError::ParseError("Tiling pattern without a valid BBox".to_string())),
+        };
+
+        // A cell that paints its own pattern would never terminate
+        if id.is_some_and(|id| self.form_stack.contains(&id)) || self.form_stack.len() >= MAX_FORM_DEPTH {
+            return Err(PDFError::ParseError("Recursive tiling pattern".to_string()));
         }
-        self.advance(); // Skip '/'
 
-        let mut name = String::new();
-        while let Some(b) = self.peek_byte() {
-            if b.is_ascii_whitespace() || b == b'[' || b == b']' || b == b'<' || b == b'>' {
-                break;
+        let content = decode_stream(stream)?;
+        let pattern_resources = dict.get(b"Resources").ok()
+            .and_then(|o| resolve(self.doc, o).as_dict().ok())
+            .and_then(|dict| self.load_resources(dict));
+        let resources = pattern_resources.as_ref().unwrap_or(self.resources);
+
+        let mut cell_parser = ContentParser::new(
+            &content,
+            &mut *self.font_manager,
+            resources,
+            self.doc,
+            self.optional_content,
+        );
+        cell_parser.form_stack = self.form_stack.clone();
+        cell_parser.form_stack.extend(id);
+        cell_parser.execute_stream();
+        let mut cell = std::mem::take(&mut cell_parser.output);
+        self.output.diagnostics.extend(std::mem::take(&mut cell.diagnostics));
+
+        Ok(TilingPattern {
+            paint_type,
+            tiling_type,
+            bbox,
+            x_step,
+            y_step,
+            matrix,
+            cell,
+        })
+    }
+
+    fn begin_marked_content(&mut self, tag: String, properties: Option<Operand>) {
+        let properties = properties.and_then(|p| self.property_list(&p));
+
+        // Optional content is tagged `/OC` with the group or membership
+        // dictionary as its property list
+        let parent_hidden = self.content_hidden();
+        let hidden = parent_hidden || (tag == "OC" && properties.as_ref()
+            .is_some_and(|(object, _)| !self.optional_content.is_visible(self.doc, object)));
+
+        let index = self.push_marked_content(tag, properties, false);
+        self.marked_content_stack.push(MarkedContentScope { index, hidden });
+    }
+
+    fn end_marked_content(&mut self) {
+        if self.marked_content_stack.pop().is_none() {
+            self.diagnose(DiagnosticKind::UnbalancedOperator, Severity::Info, "Ignoring unbalanced EMC".to_string());
+        }
+    }
+
+    fn push_marked_content(&mut self, tag: String, properties: Option<(Object, Dictionary)>, point: bool) -> usize {
+        self.output.marked_content.push(MarkedContent {
+            tag,
+            properties: properties.map(|(_, dict)| dict),
+            parent: self.marked_content_stack.last().map(|scope| scope.index),
+            point,
+        });
+        self.output.marked_content.len() - 1
+    }
+
+    /// Resolves a property list operand: an inline dictionary or the name of
+    /// a `/Properties` resource. Returns the object as given, which keeps the
+    /// reference identifying an optional content group, and its dictionary.
+    fn property_list(&mut self, operand: &Operand) -> Option<(Object, Dictionary)> {
+        let object = match operand {
+            Operand::Name(name) => match self.resources.get_properties(name) {
+                Some(object) => object.clone(),
+                None => {
+                    self.diagnose(DiagnosticKind::MissingResource, Severity::Warning, format!(
+                        "Unknown property list '{}'", name
+                    ));
+                    return None;
+                }
+            },
+            Operand::Dictionary(_) => operand.to_object(),
+            _ => return None,
+        };
+        let dict = resolve(self.doc, &object).as_dict().ok()?.clone();
+        Some((object, dict))
+    }
+
+    /// Whether objects painted now belong to hidden optional content.
+    fn content_hidden(&self) -> bool {
+        self.marked_content_stack.last().is_some_and(|scope| scope.hidden)
+    }
+
+    fn apply_ext_gstate(&mut self, name: &str) {
+        let Some(ext_gstate) = self.resources.get_ext_gstate(name) else {
+            self.diagnose(DiagnosticKind::MissingResource, Severity::Warning, format!("Unknown ExtGState '{}'", name));
+            return;
+        };
+
+        let state = &mut self.current_state;
+        if let Some(width) = ext_gstate.line_width {
+            state.line_style.width = width;
+        }
+        if let Some(cap) = ext_gstate.line_cap {
+            state.line_style.cap = cap;
+        }
+        if let Some(join) = ext_gstate.line_join {
+            state.line_style.join = join;
+        }
+        if let Some(limit) = ext_gstate.miter_limit {
+            state.line_style.miter_limit = limit;
+        }
+        if let Some((array, phase)) = &ext_gstate.dash_pattern {
+            state.line_style.dash_array = array.clone();
+            state.line_style.dash_phase = *phase;
+        }
+        if let Some(intent) = ext_gstate.rendering_intent {
+            state.rendering_intent = intent;
+        }
+        if let Some(flatness) = ext_gstate.flatness {
+            state.flatness = flatness;
+        }
+        if let Some((id, size)) = ext_gstate.font {
+            if let Some(font_name) = self.resources.font_name(id) {
+                state.font = Some(font_name.to_string());
+                state.font_size = size;
             }
-            name.push(b as char);
-            self.advance();
         }
+        if let Some(alpha) = ext_gstate.stroke_alpha {
+            state.stroke_alpha = alpha;
+        }
+        if let Some(alpha) = ext_gstate.fill_alpha {
+            state.fill_alpha = alpha;
+        }
+        if let Some(mode) = ext_gstate.blend_mode {
+            state.blend_mode = mode;
+        }
+        if let Some(soft_mask) = &ext_gstate.soft_mask {
+            state.soft_mask = soft_mask.clone();
+        }
+    }
 
-        Some(name)
+    fn lookup_color_space(&mut self, name: &str) -> Option<ColorSpace> {
+        let space = self.resources.get_color_space(name);
+        if space.is_none() {
+            self.diagnose(DiagnosticKind::MissingResource, Severity::Warning, format!("Unknown color space '{}'", name));
+        }
+        space
     }
 
-    fn parse_number(&mut self) -> Option<f32> {
-        self.skip_whitespace();
-        let start = self.position;
-        let mut has_digit = false;
-        let mut has_decimal = false;
+    fn user_space(&self, x: f32, y: f32) -> (f32, f32) {
+        self.current_state.ctm.transform_point(x, y)
+    }
 
-        while let Some(b) = self.peek_byte() {
-            match b {
-                b'0'..=b'9' => {
-                    has_digit = true;
-                    self.advance();
-                },
-                b'.' if !has_decimal => {
-                    has_decimal = true;
-                    self.advance();
-                },
-                b'-' if self.position == start => {
-                    self.advance();
-                },
-                _ if b.is_ascii_whitespace() || b == b']' => break,
-                _ => {
-                    if !has_digit {
-                        return None;
+    fn close_path(&mut self) {
+        if !self.current_path.is_empty() {
+            self.current_path.push(PathCommand::Close);
+            self.current_point = self.subpath_start;
+        }
+    }
+
+    /// Emits the current path as a vector object and starts a new path.
+    fn paint_path(&mut self, stroke: bool, fill: Option<FillRule>) {
+        if self.current_path.is_empty() {
+            self.pending_clip = None;
+            return;
+        }
+
+        if self.content_hidden() {
+            self.end_path();
+            return;
+        }
+
+        self.output.vector_objects.push(VectorObject {
+            path_data: self.current_path.clone(),
+            stroke_color: self.current_state.stroke_color.to_rgba(self.current_state.stroke_alpha),
+            fill_color: fill.map(|_| self.current_state.fill_color.to_rgba(self.current_state.fill_alpha)),
+            fill_pattern: fill.and(self.current_state.fill_pattern),
+            stroke_pattern: self.current_state.stroke_pattern.filter(|_| stroke),
+            stroked: stroke,
+            fill_rule: fill.unwrap_or(FillRule::NonZero),
+            line_style: self.current_state.line_style.transformed(&self.current_state.ctm),
+            rendering_intent: self.current_state.rendering_intent,
+            flatness: self.current_state.flatness,
+            blend_mode: self.current_state.blend_mode,
+            soft_mask: self.current_state.soft_mask.clone(),
+            clip: self.current_state.clip,
+            marked_content: self.marked_content_stack.last().map(|scope| scope.index),
+        });
+        self.end_path();
+    }
+
+    /// Ends the current path, first intersecting it with the clip if `W` or
+    /// `W*` preceded the painting operator.
+    fn end_path(&mut self) {
+        let path = std::mem::take(&mut self.current_path);
+        if let Some(fill_rule) = self.pending_clip.take() {
+            self.output.clip_paths.push(ClipPath {
+                path_data: path,
+                fill_rule,
+                parent: self.current_state.clip,
+            });
+            self.current_state.clip = Some(self.output.clip_paths.len() - 1);
+        }
+    }
+
+    /// Reads operands onto the stack until an operator keyword is found,
+    /// then builds the operator from the collected operands.
+    fn next_operator(&mut self) -> Option<Operator> {
+        while let Some(token) = self.lexer.next_token() {
+            match token {
+                Token::Operand(operand) => self.operands.push(operand),
+                Token::Keyword(keyword) => {
+                    self.operator_offset = self.lexer.token_start();
+                    self.operator = keyword;
+                    if self.operator == "BI" {
+                        self.operands.clear();
+                        return self.read_inline_image();
+                    }
+
+                    let operands = std::mem::take(&mut self.operands);
+                    match Self::build_operator(&self.operator, &operands) {
+                        Some(op) => return Some(op),
+                        None if OPERATORS.contains(&self.operator.as_str()) => {
+                            self.diagnose(DiagnosticKind::InvalidOperands, Severity::Warning, format!(
+                                "Ignoring '{}' with invalid operands {:?}", self.operator, operands
+                            ));
+                        },
+                        None => {
+                            let severity = if self.compatibility_depth > 0 { Severity::Info } else { Severity::Warning };
+                            self.diagnose(DiagnosticKind::UnknownOperator, severity, format!(
+                                "Unknown operator '{}' with {} operands", self.operator, operands.len()
+                            ));
+                        },
                     }
-                    break;
                 }
             }
         }
+        None
+    }
 
-        if !has_digit {
-            return None;
+    /// Reads an inline image's key-value pairs up to `ID`, then its data
+    /// up to `EI`.
+    fn read_inline_image(&mut self) -> Option<Operator> {
+        let mut entries = HashMap::new();
+        let mut key: Option<String> = None;
+
+        loop {
+            match self.lexer.next_token()? {
+                Token::Keyword(keyword) if keyword == "ID" => break,
+                Token::Keyword(keyword) => {
+                    let position = self.lexer.token_start();
+                    self.diagnose(DiagnosticKind::InvalidSyntax, Severity::Warning, format!(
+                        "Ignoring '{}' in inline image dictionary at position {}", keyword, position
+                    ));
+                },
+                Token::Operand(operand) => match key.take() {
+                    Some(key) => {
+                        entries.insert(key, operand);
+                    },
+                    None => key = operand.as_name().map(str::to_string),
+                },
+            }
         }
 
-        let num_str = std::str::from_utf8(&self.data[start..self.position]).ok()?;
-        num_str.parse().ok()
+        // Unfiltered data has a known size; filtered data must be scanned
+        let mut stream = inline_image_stream(&entries, Vec::new());
+        let declared = entries.get("L").or_else(|| entries.get("Length"))
+            .and_then(Operand::as_number)
+            .filter(|length| *length >= 0.0)
+            .map(|length| length as usize);
+        let length = declared.or_else(|| if stream.dict.has(b"Filter") {
+            None
+        } else {
+            self.inline_image_params(&stream.dict).and_then(|params| params.data_length())
+        });
+        stream.set_content(self.lexer.read_inline_image_data(length));
+        Some(Operator::InlineImage(stream))
+    }
+
+    fn build_operator(keyword: &str, operands: &[Operand]) -> Option<Operator> {
+        let op = match keyword {
+            "gs" => {
+                let [name] = Self::last_operands(operands)?;
+                Operator::SetGraphicsState(name.as_name()?.to_string())
+            },
+            "w" => {
+                let [width] = Self::numbers(operands)?;
+                Operator::SetLineWidth(width)
+            },
+            "J" => {
+                let [cap] = Self::numbers(operands)?;
+                Operator::SetLineCap(LineCap::from_code(cap as i32)?)
+            },
+            "j" => {
+                let [join] = Self::numbers(operands)?;
+                Operator::SetLineJoin(LineJoin::from_code(join as i32)?)
+            },
+            "M" => {
+                let [limit] = Self::numbers(operands)?;
+                Operator::SetMiterLimit(limit)
+            },
+            "d" => {
+                let [array, phase] = Self::last_operands(operands)?;
+                let array = array.as_array()?.iter().filter_map(Operand::as_number).collect();
+                Operator::SetDashPattern(array, phase.as_number()?)
+            },
+            "ri" => {
+                let [intent] = Self::last_operands(operands)?;
+                Operator::SetRenderingIntent(RenderingIntent::from_name(intent.as_name()?))
+            },
+            "i" => {
+                let [flatness] = Self::numbers(operands)?;
+                Operator::SetFlatness(flatness)
+            },
+            "G" => {
+                let [gray] = Self::numbers(operands)?;
+                Operator::SetStrokeColor(Color::Gray(gray))
+            },
+            "g" => {
+                let [gray] = Self::numbers(operands)?;
+                Operator::SetFillColor(Color::Gray(gray))
+            },
+            "RG" => {
+                let [r, g, b] = Self::numbers(operands)?;
+                Operator::SetStrokeColor(Color::RGB(r, g, b))
+            },
+            "rg" => {
+                let [r, g, b] = Self::numbers(operands)?;
+                Operator::SetFillColor(Color::RGB(r, g, b))
+            },
+            "K" => {
+                let [c, m, y, k] = Self::numbers(operands)?;
+                Operator::SetStrokeColor(Color::CMYK(c, m, y, k))
+            },
+            "k" => {
+                let [c, m, y, k] = Self::numbers(operands)?;
+                Operator::SetFillColor(Color::CMYK(c, m, y, k))
+            },
+            "CS" => {
+                let [name] = Self::last_operands(operands)?;
+                Operator::SetStrokeColorSpace(name.as_name()?.to_string())
+            },
+            "cs" => {
+                let [name] = Self::last_operands(operands)?;
+                Operator::SetFillColorSpace(name.as_name()?.to_string())
+            },
+            // A trailing name selects a pattern in the Pattern color space
+            "SCN" => match operands.last() {
+                Some(Operand::Name(name)) => Operator::SetStrokePattern(name.clone(), Self::color_components(operands)),
+                _ => Operator::SetStrokeColorComponents(Self::color_components(operands)),
+            },
+            "scn" => match operands.last() {
+                Some(Operand::Name(name)) => Operator::SetFillPattern(name.clone(), Self::color_components(operands)),
+                _ => Operator::SetFillColorComponents(Self::color_components(operands)),
+            },
+            "SC" => Operator::SetStrokeColorComponents(Self::color_components(operands)),
+            "sc" => Operator::SetFillColorComponents(Self::color_components(operands)),
+            "Do" => {
+                let [name] = Self::last_operands(operands)?;
+                Operator::DrawXObject(name.as_name()?.to_string())
+            },
+            "sh" => {
+                let [name] = Self::last_operands(operands)?;
+                Operator::PaintShading(name.as_name()?.to_string())
+            },
+            "BMC" => {
+                let [tag] = Self::last_operands(operands)?;
+                Operator::BeginMarkedContent(tag.as_name()?.to_string(), None)
+            },
+            "BDC" => {
+                let [tag, properties] = Self::last_operands(operands)?;
+                Operator::BeginMarkedContent(tag.as_name()?.to_string(), Some(properties.clone()))
+            },
+            "EMC" => Operator::EndMarkedContent,
+            "MP" => {
+                let [tag] = Self::last_operands(operands)?;
+                Operator::MarkPoint(tag.as_name()?.to_string(), None)
+            },
+            "DP" => {
+                let [tag, properties] = Self::last_operands(operands)?;
+                Operator::MarkPoint(tag.as_name()?.to_string(), Some(properties.clone()))
+            },
+            "d0" => {
+                // The vertical component is always zero for Type 3 glyphs
+                let [wx, _] = Self::numbers(operands)?;
+                Operator::SetGlyphWidth(wx)
+            },
+            "d1" => {
+                let [wx, _, llx, lly, urx, ury] = Self::numbers(operands)?;
+                Operator::SetGlyphWidthAndBoundingBox(wx, [llx, lly, urx, ury])
+            },
+            "BX" => Operator::BeginCompatibility,
+            "EX" => Operator::EndCompatibility,
+            "q" => Operator::SaveState,
+            "Q" => Operator::RestoreState,
+            "BT" => Operator::BeginText,
+            "ET" => Operator::EndText,
+            "Tf" => {
+                let [name, size] = Self::last_operands(operands)?;
+                Operator::SetFont(name.as_name()?.to_string(), size.as_number()?)
+            },
+            "Tm" => {
+                let [a, b, c, d, e, f] = Self::numbers(operands)?;
+                Operator::SetTextMatrix(a, b, c, d, e, f)
+            },
+            "Td" => {
+                let [tx, ty] = Self::numbers(operands)?;
+                Operator::MoveTextPosition(tx, ty)
+            },
+            "TD" => {
+                let [tx, ty] = Self::numbers(operands)?;
+                Operator::MoveTextPositionSetLeading(tx, ty)
+            },
+            "T*" => Operator::NextLine,
+            "Tc" => {
+                let [spacing] = Self::numbers(operands)?;
+                Operator::SetCharSpacing(spacing)
+            },
+            "Tw" => {
+                let [spacing] = Self::numbers(operands)?;
+                Operator::SetWordSpacing(spacing)
+            },
+            "Tz" => {
+                let [scale] = Self::numbers(operands)?;
+                Operator::SetHorizontalScaling(scale)
+            },
+            "Ts" => {
+                let [rise] = Self::numbers(operands)?;
+                Operator::SetTextRise(rise)
+            },
+            "Tr" => {
+                let [mode] = Self::numbers(operands)?;
+                Operator::SetRenderingMode(TextRenderMode::from_code(mode as i32)?)
+            },
+            "TL" => {
+                let [leading] = Self::numbers(operands)?;
+                Operator::SetLeading(leading)
+            },
+            "'" => {
+                let [text] = Self::last_operands(operands)?;
+                Operator::NextLineShowText(text.as_string()?.to_vec())
+            },
+            "\"" => {
+                let [word_spacing, char_spacing, text] = Self::last_operands(operands)?;
+                Operator::NextLineShowTextSpaced(
+                    word_spacing.as_number()?,
+                    char_spacing.as_number()?,
+                    text.as_string()?.to_vec(),
+                )
+            },
+            "cm" => {
+                let [a, b, c, d, e, f] = Self::numbers(operands)?;
+                Operator::ConcatMatrix(a, b, c, d, e, f)
+            },
+            "Tj" => {
+                let [text] = Self::last_operands(operands)?;
+                Operator::ShowText(text.as_string()?.to_vec())
+            },
+            "TJ" => {
+                let [array] = Self::last_operands(operands)?;
+                let elements = array.as_array()?
+                    .iter()
+                    .filter_map(|item| match item {
+                        Operand::String(text) => Some(TextElement::Text(text.clone())),
+                        Operand::Number(amount) => Some(TextElement::Offset(*amount)),
+                        _ => None,
+                    })
+                    .collect();
+                Operator::ShowTextAdjusted(elements)
+            },
+            "m" => {
+                let [x, y] = Self::numbers(operands)?;
+                Operator::MoveTo(x, y)
+            },
+            "l" => {
+                let [x, y] = Self::numbers(operands)?;
+                Operator::LineTo(x, y)
+            },
+            "c" => {
+                let [x1, y1, x2, y2, x3, y3] = Self::numbers(operands)?;
+                Operator::CurveTo(x1, y1, x2, y2, x3, y3)
+            },
+            "v" => {
+                let [x2, y2, x3, y3] = Self::numbers(operands)?;
+                Operator::CurveToInitial(x2, y2, x3, y3)
+            },
+            "y" => {
+                let [x1, y1, x3, y3] = Self::numbers(operands)?;
+                Operator::CurveToFinal(x1, y1, x3, y3)
+            },
+            "re" => {
+                let [x, y, width, height] = Self::numbers(operands)?;
+                Operator::Rectangle(x, y, width, height)
+            },
+            "h" => Operator::ClosePath,
+            "S" => Operator::Stroke,
+            "s" => Operator::CloseAndStroke,
+            "f" | "F" => Operator::Fill(FillRule::NonZero),
+            "f*" => Operator::Fill(FillRule::EvenOdd),
+            "B" => Operator::FillAndStroke(FillRule::NonZero),
+            "B*" => Operator::FillAndStroke(FillRule::EvenOdd),
+            "b" => Operator::CloseFillAndStroke(FillRule::NonZero),
+            "b*" => Operator::CloseFillAndStroke(FillRule::EvenOdd),
+            "n" => Operator::EndPath,
+            "W" => Operator::Clip(FillRule::NonZero),
+            "W*" => Operator::Clip(FillRule::EvenOdd),
+            _ => return None,
+        };
+        Some(op)
     }
 
-    fn peek_byte(&self) -> Option<u8> {
-        self.data.get(self.position).copied()
+    /// Takes the last `N` operands; extra leading operands are ignored.
+    fn last_operands<const N: usize>(operands: &[Operand]) -> Option<&[Operand; N]> {
+        let start = operands.len().checked_sub(N)?;
+        operands[start..].try_into().ok()
     }
 
-    fn advance(&mut self) {
-        self.position += 1;
+    /// Numeric color components; a trailing pattern name is skipped.
+    fn color_components(operands: &[Operand]) -> Vec<f32> {
+        operands.iter().filter_map(Operand::as_number).collect()
     }
 
-    fn skip_whitespace(&mut self) {
-        while let Some(b) = self.peek_byte() {
-            if !b.is_ascii_whitespace() && b != b'\0' {
-                break;
-            }
-            self.advance();
+    fn numbers<const N: usize>(operands: &[Operand]) -> Option<[f32; N]> {
+        let operands: &[Operand; N] = Self::last_operands(operands)?;
+        let mut values = [0.0; N];
+        for (value, operand) in values.iter_mut().zip(operands) {
+            *value = operand.as_number()?;
         }
+        Some(values)
     }
 
     fn handle_save_state(&mut self) {
         self.graphics_state_stack.push(self.current_state.clone());
     }
 
-    fn handle_restore_state(&mut self) -> Result<(), PDFError> {
-        if let Some(state) = self.graphics_state_stack.pop() {
-            self.current_state = state;
-            Ok(())
-        } else {
-            Err(PDFError::ParseError("Graphics state stack underflow".into()))
+    fn handle_restore_state(&mut self) {
+        match self.graphics_state_stack.pop() {
+            Some(state) => self.current_state = state,
+            // Unbalanced `Q` operators are common in generated PDFs; keep the
+            // current state rather than aborting the page
+            None => self.diagnose(DiagnosticKind::UnbalancedOperator, Severity::Warning, "Ignoring unbalanced Q".to_string()),
         }
     }
 
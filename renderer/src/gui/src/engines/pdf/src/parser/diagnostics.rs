@@ -0,0 +1,98 @@
+// src/engines/pdf/src/parser/diagnostics.rs
+use lopdf::ObjectId;
+
+/// How much a problem affects the rendered page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Recovered from without visible effect.
+    Info,
+    /// The page may render differently than intended.
+    Warning,
+    /// Content was dropped from the page.
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// A keyword that is not a content stream operator.
+    UnknownOperator,
+    /// A known operator with missing or mistyped operands.
+    InvalidOperands,
+    /// Malformed data inside an operator, such as an inline image
+    /// dictionary.
+    InvalidSyntax,
+    /// `Q` or `EMC` without a matching `q` or `BMC`/`BDC`.
+    UnbalancedOperator,
+    /// A name not found in the current resource dictionary.
+    MissingResource,
+    /// A stream, image, shading or pattern that could not be decoded.
+    DecodeFailed,
+    /// A feature the parser does not handle.
+    Unsupported,
+    /// Nesting of forms, patterns or glyph procedures that was cut off.
+    RecursionLimit,
+}
+
+impl DiagnosticKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticKind::UnknownOperator => "unknown-operator",
+            DiagnosticKind::InvalidOperands => "invalid-operands",
+            DiagnosticKind::InvalidSyntax => "invalid-syntax",
+            DiagnosticKind::UnbalancedOperator => "unbalanced-operator",
+            DiagnosticKind::MissingResource => "missing-resource",
+            DiagnosticKind::DecodeFailed => "decode-failed",
+            DiagnosticKind::Unsupported => "unsupported",
+            DiagnosticKind::RecursionLimit => "recursion-limit",
+        }
+    }
+}
+
+/// A problem found while parsing a page.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub severity: Severity,
+    /// The content stream the problem was found in: a form XObject, tiling
+    /// pattern or glyph procedure, or `None` for the page's own contents.
+    pub stream: Option<ObjectId>,
+    /// Byte offset of the operator within `stream`.
+    pub offset: Option<usize>,
+    pub operator: Option<String>,
+    pub message: String,
+}
+
+/// Diagnostics collected while parsing, in the order they were found.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    entries: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.entries.push(diagnostic);
+    }
+
+    /// Records a problem that is not tied to a content stream operator.
+    pub fn report(&mut self, kind: DiagnosticKind, severity: Severity, message: String) {
+        self.push(Diagnostic { kind, severity, stream: None, offset: None, operator: None, message });
+    }
+
+    pub fn extend(&mut self, other: Diagnostics) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.entries.iter()
+    }
+}
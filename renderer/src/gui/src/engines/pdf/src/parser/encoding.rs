@@ -0,0 +1,510 @@
+// src/engines/pdf/src/parser/encoding.rs
+use std::collections::HashMap;
+use lopdf::{Document, Dictionary, Object};
+use super::{FontType, resolve};
+use super::font::{FontDescriptor, FontFlags};
+use super::font_program::{FontFormat, FontProgram};
+use super::glyph_list::GLYPH_LIST;
+
+/// Maps the single-byte codes of a simple font to glyph names, and through
+/// them to Unicode.
+#[derive(Debug, Clone)]
+pub struct FontEncoding {
+    names: Vec<Option<String>>,
+    text: Vec<Option<String>>,
+}
+
+impl FontEncoding {
+    /// Builds the encoding of a simple font from its `/Encoding` entry: a
+    /// base encoding, modified by a `/Differences` array. Without a base
+    /// encoding the font's built-in one applies.
+    pub fn from_font(
+        doc: &Document,
+        dict: &Dictionary,
+        font_type: FontType,
+        base_font: &str,
+        descriptor: Option<&FontDescriptor>,
+        program: Option<&FontProgram>,
+    ) -> Self {
+        let (base_encoding, differences_dict) = match dict.get(b"Encoding").ok().map(|o| resolve(doc, o)) {
+            Some(Object::Name(name)) => (Some(name.as_slice()), None),
+            Some(Object::Dictionary(encoding)) => (
+                encoding.get(b"BaseEncoding").ok().and_then(|o| resolve(doc, o).as_name().ok()),
+                Some(encoding),
+            ),
+            _ => (None, None),
+        };
+
+        let mut dingbats = false;
+        let mut names = match base_encoding.and_then(named_encoding) {
+            Some(table) => table_names(table),
+            None => {
+                let (names, is_dingbats) = builtin_encoding(font_type, base_font, descriptor, program);
+                dingbats = is_dingbats;
+                names
+            },
+        };
+        if let Some(encoding) = differences_dict {
+            for (code, name) in differences(doc, encoding) {
+                if let Some(entry) = names.get_mut(code as usize) {
+                    *entry = Some(name);
+                }
+            }
+        }
+
+        let text = names.iter()
+            .enumerate()
+            .map(|(code, name)| match name {
+                Some(name) => glyph_to_unicode(name),
+                None if dingbats => ZAPF_DINGBATS_ENCODING[code].map(String::from),
+                None => None,
+            })
+            .collect();
+        FontEncoding { names, text }
+    }
+
+    pub fn glyph_name(&self, code: u32) -> Option<&str> {
+        self.names.get(code as usize)?.as_deref()
+    }
+
+    /// The text a code stands for, when its glyph has a known name.
+    pub fn to_unicode(&self, code: u32) -> Option<&str> {
+        self.text.get(code as usize)?.as_deref()
+    }
+}
+
+/// Reads the `/Differences` array of an encoding dictionary, which lists a
+/// starting code followed by the glyph names of consecutive codes.
+fn differences(doc: &Document, encoding: &Dictionary) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    let Some(array) = encoding.get(b"Differences").ok().and_then(|o| resolve(doc, o).as_array().ok()) else {
+        return names;
+    };
+
+    let mut code = 0;
+    for entry in array {
+        match entry {
+            Object::Integer(start) => code = (*start).max(0) as u32,
+            Object::Name(name) => {
+                names.insert(code, String::from_utf8_lossy(name).into_owned());
+                code += 1;
+            },
+            _ => {},
+        }
+    }
+    names
+}
+
+/// Looks up an encoding by the name used for `/Encoding` or
+/// `/BaseEncoding`.
+fn named_encoding(name: &[u8]) -> Option<&'static [Option<&'static str>; 256]> {
+    match name {
+        b"StandardEncoding" => Some(&STANDARD_ENCODING),
+        b"WinAnsiEncoding" => Some(&WIN_ANSI_ENCODING),
+        b"MacRomanEncoding" => Some(&MAC_ROMAN_ENCODING),
+        b"MacExpertEncoding" => Some(&MAC_EXPERT_ENCODING),
+        _ => None,
+    }
+}
+
+fn table_names(table: &[Option<&str>; 256]) -> Vec<Option<String>> {
+    table.iter().map(|name| name.map(String::from)).collect()
+}
+
+/// The encoding a font uses when its dictionary names none, and whether it
+/// is that of ZapfDingbats, whose glyphs are not named.
+fn builtin_encoding(
+    font_type: FontType,
+    base_font: &str,
+    descriptor: Option<&FontDescriptor>,
+    program: Option<&FontProgram>,
+) -> (Vec<Option<String>>, bool) {
+    // Embedded Type 1 programs declare their encoding in the cleartext part
+    if let Some(names) = program.and_then(type1_encoding) {
+        return (names, false);
+    }
+
+    // Drop the subset tag, six capital letters followed by `+`
+    let standard_name = match base_font.split_once('+') {
+        Some((tag, name)) if tag.len() == 6 && tag.bytes().all(|b| b.is_ascii_uppercase()) => name,
+        _ => base_font,
+    };
+    match standard_name {
+        "Symbol" => return (table_names(&SYMBOL_ENCODING), false),
+        "ZapfDingbats" => return (vec![None; 256], true),
+        _ => {},
+    }
+
+    // Codes of embedded symbolic fonts select glyphs directly, and Type 3
+    // glyphs are only named by `/Differences`
+    let symbolic = descriptor.is_some_and(|d| {
+        d.flags.contains(FontFlags::SYMBOLIC) && !d.flags.contains(FontFlags::NONSYMBOLIC)
+    });
+    if font_type == FontType::Type3 || (symbolic && program.is_some()) {
+        return (vec![None; 256], false);
+    }
+    (table_names(&STANDARD_ENCODING), false)
+}
+
+/// Reads the `/Encoding` of a Type 1 program: either `StandardEncoding` or
+/// an array filled by `dup <code> /<name> put` entries.
+fn type1_encoding(program: &FontProgram) -> Option<Vec<Option<String>>> {
+    if program.format != FontFormat::Type1 {
+        return None;
+    }
+    let cleartext = &program.data[..program.segments.map_or(program.data.len(), |segments| segments[0])];
+    let start = cleartext.windows(9).position(|window| window == b"/Encoding")? + 9;
+
+    let mut tokens = type1_tokens(&cleartext[start..]);
+    if tokens.next()? == b"StandardEncoding" {
+        return Some(table_names(&STANDARD_ENCODING));
+    }
+
+    let mut names = vec![None; 256];
+    let mut previous: [&[u8]; 3] = [b"", b"", b""];
+    for token in tokens {
+        match token {
+            b"def" | b"readonly" => break,
+            b"put" => if let [b"dup", code, name] = previous {
+                let code = std::str::from_utf8(code).ok().and_then(|code| code.parse::<usize>().ok());
+                if let (Some(entry), Some(name)) = (code.and_then(|code| names.get_mut(code)), name.strip_prefix(b"/")) {
+                    *entry = Some(String::from_utf8_lossy(name).into_owned());
+                }
+            },
+            _ => {},
+        }
+        previous = [previous[1], previous[2], token];
+    }
+    Some(names)
+}
+
+/// Splits PostScript source into whitespace-separated tokens, starting a
+/// new token at each name or bracket.
+fn type1_tokens(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut position = 0;
+    std::iter::from_fn(move || {
+        while data.get(position).is_some_and(u8::is_ascii_whitespace) {
+            position += 1;
+        }
+        if position >= data.len() {
+            return None;
+        }
+        let start = position;
+        position += 1;
+        while data.get(position).is_some_and(|&b| !b.is_ascii_whitespace() && !matches!(b, b'/' | b'[' | b']' | b'{' | b'}')) {
+            position += 1;
+        }
+        Some(&data[start..position])
+    })
+}
+
+/// Maps a glyph name to Unicode following the Adobe Glyph List
+/// specification: a suffix after `.` is dropped, ligatures are named by
+/// their components joined with `_`, and components are either listed
+/// names or `uniXXXX`/`uXXXX[XX]` code points. Ligatures listed under
+/// their own name, such as `f_f_i`, map to their ligature character.
+pub fn glyph_to_unicode(name: &str) -> Option<String> {
+    let name = name.split('.').next().unwrap_or_default();
+    if let Ok(index) = GLYPH_LIST.binary_search_by(|(listed, _)| listed.cmp(&name)) {
+        return Some(GLYPH_LIST[index].1.to_string());
+    }
+    let text: String = name.split('_')
+        .filter_map(glyph_component_to_unicode)
+        .collect();
+    (!text.is_empty()).then_some(text)
+}
+
+fn glyph_component_to_unicode(component: &str) -> Option<String> {
+    if let Ok(index) = GLYPH_LIST.binary_search_by(|(name, _)| name.cmp(&component)) {
+        return Some(GLYPH_LIST[index].1.to_string());
+    }
+    let code_point = |hex: &str| {
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+    };
+    if let Some(hex) = component.strip_prefix("uni").filter(|hex| !hex.is_empty() && hex.len() % 4 == 0) {
+        return (0..hex.len()).step_by(4)
+            .map(|start| code_point(&hex[start..start + 4]))
+            .collect();
+    }
+    component.strip_prefix('u')
+        .filter(|hex| (4..=6).contains(&hex.len()))
+        .and_then(code_point)
+        .map(String::from)
+}
+
+/// `/StandardEncoding`, the built-in encoding of most Latin Type 1 fonts.
+pub const STANDARD_ENCODING: [Option<&str>; 256] = [
+    /* 0x00 */ None, None, None, None, None, None, None, None,
+    /* 0x08 */ None, None, None, None, None, None, None, None,
+    /* 0x10 */ None, None, None, None, None, None, None, None,
+    /* 0x18 */ None, None, None, None, None, None, None, None,
+    /* 0x20 */ Some("space"), Some("exclam"), Some("quotedbl"), Some("numbersign"), Some("dollar"), Some("percent"), Some("ampersand"), Some("quoteright"),
+    /* 0x28 */ Some("parenleft"), Some("parenright"), Some("asterisk"), Some("plus"), Some("comma"), Some("hyphen"), Some("period"), Some("slash"),
+    /* 0x30 */ Some("zero"), Some("one"), Some("two"), Some("three"), Some("four"), Some("five"), Some("six"), Some("seven"),
+    /* 0x38 */ Some("eight"), Some("nine"), Some("colon"), Some("semicolon"), Some("less"), Some("equal"), Some("greater"), Some("question"),
+    /* 0x40 */ Some("at"), Some("A"), Some("B"), Some("C"), Some("D"), Some("E"), Some("F"), Some("G"),
+    /* 0x48 */ Some("H"), Some("I"), Some("J"), Some("K"), Some("L"), Some("M"), Some("N"), Some("O"),
+    /* 0x50 */ Some("P"), Some("Q"), Some("R"), Some("S"), Some("T"), Some("U"), Some("V"), Some("W"),
+    /* 0x58 */ Some("X"), Some("Y"), Some("Z"), Some("bracketleft"), Some("backslash"), Some("bracketright"), Some("asciicircum"), Some("underscore"),
+    /* 0x60 */ Some("quoteleft"), Some("a"), Some("b"), Some("c"), Some("d"), Some("e"), Some("f"), Some("g"),
+    /* 0x68 */ Some("h"), Some("i"), Some("j"), Some("k"), Some("l"), Some("m"), Some("n"), Some("o"),
+    /* 0x70 */ Some("p"), Some("q"), Some("r"), Some("s"), Some("t"), Some("u"), Some("v"), Some("w"),
+    /* 0x78 */ Some("x"), Some("y"), Some("z"), Some("braceleft"), Some("bar"), Some("braceright"), Some("asciitilde"), None,
+    /* 0x80 */ None, None, None, None, None, None, None, None,
+    /* 0x88 */ None, None, None, None, None, None, None, None,
+    /* 0x90 */ None, None, None, None, None, None, None, None,
+    /* 0x98 */ None, None, None, None, None, None, None, None,
+    /* 0xA0 */ None, Some("exclamdown"), Some("cent"), Some("sterling"), Some("fraction"), Some("yen"), Some("florin"), Some("section"),
+    /* 0xA8 */ Some("currency"), Some("quotesingle"), Some("quotedblleft"), Some("guillemotleft"), Some("guilsinglleft"), Some("guilsinglright"), Some("fi"), Some("fl"),
+    /* 0xB0 */ None, Some("endash"), Some("dagger"), Some("daggerdbl"), Some("periodcentered"), None, Some("paragraph"), Some("bullet"),
+    /* 0xB8 */ Some("quotesinglbase"), Some("quotedblbase"), Some("quotedblright"), Some("guillemotright"), Some("ellipsis"), Some("perthousand"), None, Some("questiondown"),
+    /* 0xC0 */ None, Some("grave"), Some("acute"), Some("circumflex"), Some("tilde"), Some("macron"), Some("breve"), Some("dotaccent"),
+    /* 0xC8 */ Some("dieresis"), None, Some("ring"), Some("cedilla"), None, Some("hungarumlaut"), Some("ogonek"), Some("caron"),
+    /* 0xD0 */ Some("emdash"), None, None, None, None, None, None, None,
+    /* 0xD8 */ None, None, None, None, None, None, None, None,
+    /* 0xE0 */ None, Some("AE"), None, Some("ordfeminine"), None, None, None, None,
+    /* 0xE8 */ Some("Lslash"), Some("Oslash"), Some("OE"), Some("ordmasculine"), None, None, None, None,
+    /* 0xF0 */ None, Some("ae"), None, None, None, Some("dotlessi"), None, None,
+    /* 0xF8 */ Some("lslash"), Some("oslash"), Some("oe"), Some("germandbls"), None, None, None, None,
+];
+
+/// `/WinAnsiEncoding`, Windows code page 1252.
+pub const WIN_ANSI_ENCODING: [Option<&str>; 256] = [
+    /* 0x00 */ None, None, None, None, None, None, None, None,
+    /* 0x08 */ None, None, None, None, None, None, None, None,
+    /* 0x10 */ None, None, None, None, None, None, None, None,
+    /* 0x18 */ None, None, None, None, None, None, None, None,
+    /* 0x20 */ Some("space"), Some("exclam"), Some("quotedbl"), Some("numbersign"), Some("dollar"), Some("percent"), Some("ampersand"), Some("quotesingle"),
+    /* 0x28 */ Some("parenleft"), Some("parenright"), Some("asterisk"), Some("plus"), Some("comma"), Some("hyphen"), Some("period"), Some("slash"),
+    /* 0x30 */ Some("zero"), Some("one"), Some("two"), Some("three"), Some("four"), Some("five"), Some("six"), Some("seven"),
+    /* 0x38 */ Some("eight"), Some("nine"), Some("colon"), Some("semicolon"), Some("less"), Some("equal"), Some("greater"), Some("question"),
+    /* 0x40 */ Some("at"), Some("A"), Some("B"), Some("C"), Some("D"), Some("E"), Some("F"), Some("G"),
+    /* 0x48 */ Some("H"), Some("I"), Some("J"), Some("K"), Some("L"), Some("M"), Some("N"), Some("O"),
+    /* 0x50 */ Some("P"), Some("Q"), Some("R"), Some("S"), Some("T"), Some("U"), Some("V"), Some("W"),
+    /* 0x58 */ Some("X"), Some("Y"), Some("Z"), Some("bracketleft"), Some("backslash"), Some("bracketright"), Some("asciicircum"), Some("underscore"),
+    /* 0x60 */ Some("grave"), Some("a"), Some("b"), Some("c"), Some("d"), Some("e"), Some("f"), Some("g"),
+    /* 0x68 */ Some("h"), Some("i"), Some("j"), Some("k"), Some("l"), Some("m"), Some("n"), Some("o"),
+    /* 0x70 */ Some("p"), Some("q"), Some("r"), Some("s"), Some("t"), Some("u"), Some("v"), Some("w"),
+    /* 0x78 */ Some("x"), Some("y"), Some("z"), Some("braceleft"), Some("bar"), Some("braceright"), Some("asciitilde"), Some("bullet"),
+    /* 0x80 */ Some("Euro"), Some("bullet"), Some("quotesinglbase"), Some("florin"), Some("quotedblbase"), Some("ellipsis"), Some("dagger"), Some("daggerdbl"),
+    /* 0x88 */ Some("circumflex"), Some("perthousand"), Some("Scaron"), Some("guilsinglleft"), Some("OE"), Some("bullet"), Some("Zcaron"), Some("bullet"),
+    /* 0x90 */ Some("bullet"), Some("quoteleft"), Some("quoteright"), Some("quotedblleft"), Some("quotedblright"), Some("bullet"), Some("endash"), Some("emdash"),
+    /* 0x98 */ Some("tilde"), Some("trademark"), Some("scaron"), Some("guilsinglright"), Some("oe"), Some("bullet"), Some("zcaron"), Some("Ydieresis"),
+    /* 0xA0 */ Some("space"), Some("exclamdown"), Some("cent"), Some("sterling"), Some("currency"), Some("yen"), Some("brokenbar"), Some("section"),
+    /* 0xA8 */ Some("dieresis"), Some("copyright"), Some("ordfeminine"), Some("guillemotleft"), Some("logicalnot"), Some("hyphen"), Some("registered"), Some("macron"),
+    /* 0xB0 */ Some("degree"), Some("plusminus"), Some("twosuperior"), Some("threesuperior"), Some("acute"), Some("mu"), Some("paragraph"), Some("periodcentered"),
+    /* 0xB8 */ Some("cedilla"), Some("onesuperior"), Some("ordmasculine"), Some("guillemotright"), Some("onequarter"), Some("onehalf"), Some("threequarters"), Some("questiondown"),
+    /* 0xC0 */ Some("Agrave"), Some("Aacute"), Some("Acircumflex"), Some("Atilde"), Some("Adieresis"), Some("Aring"), Some("AE"), Some("Ccedilla"),
+    /* 0xC8 */ Some("Egrave"), Some("Eacute"), Some("Ecircumflex"), Some("Edieresis"), Some("Igrave"), Some("Iacute"), Some("Icircumflex"), Some("Idieresis"),
+    /* 0xD0 */ Some("Eth"), Some("Ntilde"), Some("Ograve"), Some("Oacute"), Some("Ocircumflex"), Some("Otilde"), Some("Odieresis"), Some("multiply"),
+    /* 0xD8 */ Some("Oslash"), Some("Ugrave"), Some("Uacute"), Some("Ucircumflex"), Some("Udieresis"), Some("Yacute"), Some("Thorn"), Some("germandbls"),
+    /* 0xE0 */ Some("agrave"), Some("aacute"), Some("acircumflex"), Some("atilde"), Some("adieresis"), Some("aring"), Some("ae"), Some("ccedilla"),
+    /* 0xE8 */ Some("egrave"), Some("eacute"), Some("ecircumflex"), Some("edieresis"), Some("igrave"), Some("iacute"), Some("icircumflex"), Some("idieresis"),
+    /* 0xF0 */ Some("eth"), Some("ntilde"), Some("ograve"), Some("oacute"), Some("ocircumflex"), Some("otilde"), Some("odieresis"), Some("divide"),
+    /* 0xF8 */ Some("oslash"), Some("ugrave"), Some("uacute"), Some("ucircumflex"), Some("udieresis"), Some("yacute"), Some("thorn"), Some("ydieresis"),
+];
+
+/// `/MacRomanEncoding`, the Mac OS standard Latin encoding.
+pub const MAC_ROMAN_ENCODING: [Option<&str>; 256] = [
+    /* 0x00 */ None, None, None, None, None, None, None, None,
+    /* 0x08 */ None, None, None, None, None, None, None, None,
+    /* 0x10 */ None, None, None, None, None, None, None, None,
+    /* 0x18 */ None, None, None, None, None, None, None, None,
+    /* 0x20 */ Some("space"), Some("exclam"), Some("quotedbl"), Some("numbersign"), Some("dollar"), Some("percent"), Some("ampersand"), Some("quotesingle"),
+    /* 0x28 */ Some("parenleft"), Some("parenright"), Some("asterisk"), Some("plus"), Some("comma"), Some("hyphen"), Some("period"), Some("slash"),
+    /* 0x30 */ Some("zero"), Some("one"), Some("two"), Some("three"), Some("four"), Some("five"), Some("six"), Some("seven"),
+    /* 0x38 */ Some("eight"), Some("nine"), Some("colon"), Some("semicolon"), Some("less"), Some("equal"), Some("greater"), Some("question"),
+    /* 0x40 */ Some("at"), Some("A"), Some("B"), Some("C"), Some("D"), Some("E"), Some("F"), Some("G"),
+    /* 0x48 */ Some("H"), Some("I"), Some("J"), Some("K"), Some("L"), Some("M"), Some("N"), Some("O"),
+    /* 0x50 */ Some("P"), Some("Q"), Some("R"), Some("S"), Some("T"), Some("U"), Some("V"), Some("W"),
+    /* 0x58 */ Some("X"), Some("Y"), Some("Z"), Some("bracketleft"), Some("backslash"), Some("bracketright"), Some("asciicircum"), Some("underscore"),
+    /* 0x60 */ Some("grave"), Some("a"), Some("b"), Some("c"), Some("d"), Some("e"), Some("f"), Some("g"),
+    /* 0x68 */ Some("h"), Some("i"), Some("j"), Some("k"), Some("l"), Some("m"), Some("n"), Some("o"),
+    /* 0x70 */ Some("p"), Some("q"), Some("r"), Some("s"), Some("t"), Some("u"), Some("v"), Some("w"),
+    /* 0x78 */ Some("x"), Some("y"), Some("z"), Some("braceleft"), Some("bar"), Some("braceright"), Some("asciitilde"), None,
+    /* 0x80 */ Some("Adieresis"), Some("Aring"), Some("Ccedilla"), Some("Eacute"), Some("Ntilde"), Some("Odieresis"), Some("Udieresis"), Some("aacute"),
+    /* 0x88 */ Some("agrave"), Some("acircumflex"), Some("adieresis"), Some("atilde"), Some("aring"), Some("ccedilla"), Some("eacute"), Some("egrave"),
+    /* 0x90 */ Some("ecircumflex"), Some("edieresis"), Some("iacute"), Some("igrave"), Some("icircumflex"), Some("idieresis"), Some("ntilde"), Some("oacute"),
+    /* 0x98 */ Some("ograve"), Some("ocircumflex"), Some("odieresis"), Some("otilde"), Some("uacute"), Some("ugrave"), Some("ucircumflex"), Some("udieresis"),
+    /* 0xA0 */ Some("dagger"), Some("degree"), Some("cent"), Some("sterling"), Some("section"), Some("bullet"), Some("paragraph"), Some("germandbls"),
+    /* 0xA8 */ Some("registered"), Some("copyright"), Some("trademark"), Some("acute"), Some("dieresis"), Some("notequal"), Some("AE"), Some("Oslash"),
+    /* 0xB0 */ Some("infinity"), Some("plusminus"), Some("lessequal"), Some("greaterequal"), Some("yen"), Some("mu"), Some("partialdiff"), Some("summation"),
+    /* 0xB8 */ Some("product"), Some("pi"), Some("integral"), Some("ordfeminine"), Some("ordmasculine"), Some("Omega"), Some("ae"), Some("oslash"),
+    /* 0xC0 */ Some("questiondown"), Some("exclamdown"), Some("logicalnot"), Some("radical"), Some("florin"), Some("approxequal"), Some("Delta"), Some("guillemotleft"),
+    /* 0xC8 */ Some("guillemotright"), Some("ellipsis"), Some("space"), Some("Agrave"), Some("Atilde"), Some("Otilde"), Some("OE"), Some("oe"),
+    /* 0xD0 */ Some("endash"), Some("emdash"), Some("quotedblleft"), Some("quotedblright"), Some("quoteleft"), Some("quoteright"), Some("divide"), Some("lozenge"),
+    /* 0xD8 */ Some("ydieresis"), Some("Ydieresis"), Some("fraction"), Some("currency"), Some("guilsinglleft"), Some("guilsinglright"), Some("fi"), Some("fl"),
+    /* 0xE0 */ Some("daggerdbl"), Some("periodcentered"), Some("quotesinglbase"), Some("quotedblbase"), Some("perthousand"), Some("Acircumflex"), Some("Ecircumflex"), Some("Aacute"),
+    /* 0xE8 */ Some("Edieresis"), Some("Egrave"), Some("Iacute"), Some("Icircumflex"), Some("Idieresis"), Some("Igrave"), Some("Oacute"), Some("Ocircumflex"),
+    /* 0xF0 */ None, Some("Ograve"), Some("Uacute"), Some("Ucircumflex"), Some("Ugrave"), Some("dotlessi"), Some("circumflex"), Some("tilde"),
+    /* 0xF8 */ Some("macron"), Some("breve"), Some("dotaccent"), Some("ring"), Some("cedilla"), Some("hungarumlaut"), Some("ogonek"), Some("caron"),
+];
+
+/// `/MacExpertEncoding`, for expert fonts with small capitals and fractions.
+pub const MAC_EXPERT_ENCODING: [Option<&str>; 256] = [
+    /* 0x00 */ None, None, None, None, None, None, None, None,
+    /* 0x08 */ None, None, None, None, None, None, None, None,
+    /* 0x10 */ None, None, None, None, None, None, None, None,
+    /* 0x18 */ None, None, None, None, None, None, None, None,
+    /* 0x20 */ Some("space"), Some("exclamsmall"), Some("Hungarumlautsmall"), Some("centoldstyle"), Some("dollaroldstyle"), Some("dollarsuperior"), Some("ampersandsmall"), Some("Acutesmall"),
+    /* 0x28 */ Some("parenleftsuperior"), Some("parenrightsuperior"), Some("twodotenleader"), Some("onedotenleader"), Some("comma"), Some("hyphen"), Some("period"), Some("fraction"),
+    /* 0x30 */ Some("zerooldstyle"), Some("oneoldstyle"), Some("twooldstyle"), Some("threeoldstyle"), Some("fouroldstyle"), Some("fiveoldstyle"), Some("sixoldstyle"), Some("sevenoldstyle"),
+    /* 0x38 */ Some("eightoldstyle"), Some("nineoldstyle"), Some("colon"), Some("semicolon"), None, Some("threequartersemdash"), None, Some("questionsmall"),
+    /* 0x40 */ None, None, None, None, Some("Ethsmall"), None, None, Some("onequarter"),
+    /* 0x48 */ Some("onehalf"), Some("threequarters"), Some("oneeighth"), Some("threeeighths"), Some("fiveeighths"), Some("seveneighths"), Some("onethird"), Some("twothirds"),
+    /* 0x50 */ None, None, None, None, None, None, Some("ff"), Some("fi"),
+    /* 0x58 */ Some("fl"), Some("ffi"), Some("ffl"), Some("parenleftinferior"), None, Some("parenrightinferior"), Some("Circumflexsmall"), Some("hypheninferior"),
+    /* 0x60 */ Some("Gravesmall"), Some("Asmall"), Some("Bsmall"), Some("Csmall"), Some("Dsmall"), Some("Esmall"), Some("Fsmall"), Some("Gsmall"),
+    /* 0x68 */ Some("Hsmall"), Some("Ismall"), Some("Jsmall"), Some("Ksmall"), Some("Lsmall"), Some("Msmall"), Some("Nsmall"), Some("Osmall"),
+    /* 0x70 */ Some("Psmall"), Some("Qsmall"), Some("Rsmall"), Some("Ssmall"), Some("Tsmall"), Some("Usmall"), Some("Vsmall"), Some("Wsmall"),
+    /* 0x78 */ Some("Xsmall"), Some("Ysmall"), Some("Zsmall"), Some("colonmonetary"), Some("onefitted"), Some("rupiah"), Some("Tildesmall"), None,
+    /* 0x80 */ None, Some("asuperior"), Some("centsuperior"), None, None, None, None, Some("Aacutesmall"),
+    /* 0x88 */ Some("Agravesmall"), Some("Acircumflexsmall"), Some("Adieresissmall"), Some("Atildesmall"), Some("Aringsmall"), Some("Ccedillasmall"), Some("Eacutesmall"), Some("Egravesmall"),
+    /* 0x90 */ Some("Ecircumflexsmall"), Some("Edieresissmall"), Some("Iacutesmall"), Some("Igravesmall"), Some("Icircumflexsmall"), Some("Idieresissmall"), Some("Ntildesmall"), Some("Oacutesmall"),
+    /* 0x98 */ Some("Ogravesmall"), Some("Ocircumflexsmall"), Some("Odieresissmall"), Some("Otildesmall"), Some("Uacutesmall"), Some("Ugravesmall"), Some("Ucircumflexsmall"), Some("Udieresissmall"),
+    /* 0xA0 */ None, Some("eightsuperior"), Some("fourinferior"), Some("threeinferior"), Some("sixinferior"), Some("eightinferior"), Some("seveninferior"), Some("Scaronsmall"),
+    /* 0xA8 */ None, Some("centinferior"), Some("twoinferior"), None, Some("Dieresissmall"), None, Some("Caronsmall"), Some("osuperior"),
+    /* 0xB0 */ Some("fiveinferior"), None, Some("commainferior"), Some("periodinferior"), Some("Yacutesmall"), None, Some("dollarinferior"), None,
+    /* 0xB8 */ None, Some("Thornsmall"), None, Some("nineinferior"), Some("zeroinferior"), Some("Zcaronsmall"), Some("AEsmall"), Some("Oslashsmall"),
+    /* 0xC0 */ Some("questiondownsmall"), Some("oneinferior"), Some("Lslashsmall"), None, None, None, None, None,
+    /* 0xC8 */ None, Some("Cedillasmall"), None, None, None, None, None, Some("OEsmall"),
+    /* 0xD0 */ Some("figuredash"), Some("hyphensuperior"), None, None, None, None, Some("exclamdownsmall"), None,
+    /* 0xD8 */ Some("Ydieresissmall"), None, Some("onesuperior"), Some("twosuperior"), Some("threesuperior"), Some("foursuperior"), Some("fivesuperior"), Some("sixsuperior"),
+    /* 0xE0 */ Some("sevensuperior"), Some("ninesuperior"), Some("zerosuperior"), None, Some("esuperior"), Some("rsuperior"), Some("tsuperior"), None,
+    /* 0xE8 */ None, Some("isuperior"), Some("ssuperior"), Some("dsuperior"), None, None, None, None,
+    /* 0xF0 */ None, Some("lsuperior"), Some("Ogoneksmall"), Some("Brevesmall"), Some("Macronsmall"), Some("bsuperior"), Some("nsuperior"), Some("msuperior"),
+    /* 0xF8 */ Some("commasuperior"), Some("periodsuperior"), Some("Dotaccentsmall"), Some("Ringsmall"), None, None, None, None,
+];
+
+/// Built-in encoding of the standard Symbol font.
+pub const SYMBOL_ENCODING: [Option<&str>; 256] = [
+    /* 0x00 */ None, None, None, None, None, None, None, None,
+    /* 0x08 */ None, None, None, None, None, None, None, None,
+    /* 0x10 */ None, None, None, None, None, None, None, None,
+    /* 0x18 */ None, None, None, None, None, None, None, None,
+    /* 0x20 */ Some("space"), Some("exclam"), Some("universal"), Some("numbersign"), Some("existential"), Some("percent"), Some("ampersand"), Some("suchthat"),
+    /* 0x28 */ Some("parenleft"), Some("parenright"), Some("asteriskmath"), Some("plus"), Some("comma"), Some("minus"), Some("period"), Some("slash"),
+    /* 0x30 */ Some("zero"), Some("one"), Some("two"), Some("three"), Some("four"), Some("five"), Some("six"), Some("seven"),
+    /* 0x38 */ Some("eight"), Some("nine"), Some("colon"), Some("semicolon"), Some("less"), Some("equal"), Some("greater"), Some("question"),
+    /* 0x40 */ Some("congruent"), Some("Alpha"), Some("Beta"), Some("Chi"), Some("Delta"), Some("Epsilon"), Some("Phi"), Some("Gamma"),
+    /* 0x48 */ Some("Eta"), Some("Iota"), Some("theta1"), Some("Kappa"), Some("Lambda"), Some("Mu"), Some("Nu"), Some("Omicron"),
+    /* 0x50 */ Some("Pi"), Some("Theta"), Some("Rho"), Some("Sigma"), Some("Tau"), Some("Upsilon"), Some("sigma1"), Some("Omega"),
+    /* 0x58 */ Some("Xi"), Some("Psi"), Some("Zeta"), Some("bracketleft"), Some("therefore"), Some("bracketright"), Some("perpendicular"), Some("underscore"),
+    /* 0x60 */ Some("radicalex"), Some("alpha"), Some("beta"), Some("chi"), Some("delta"), Some("epsilon"), Some("phi"), Some("gamma"),
+    /* 0x68 */ Some("eta"), Some("iota"), Some("phi2"), Some("kappa"), Some("lambda"), Some("mu"), Some("nu"), Some("omicron"),
+    /* 0x70 */ Some("pi"), Some("theta"), Some("rho"), Some("sigma"), Some("tau"), Some("upsilon"), Some("omega1"), Some("omega"),
+    /* 0x78 */ Some("xi"), Some("psi"), Some("zeta"), Some("braceleft"), Some("bar"), Some("braceright"), Some("similar"), None,
+    /* 0x80 */ None, None, None, None, None, None, None, None,
+    /* 0x88 */ None, None, None, None, None, None, None, None,
+    /* 0x90 */ None, None, None, None, None, None, None, None,
+    /* 0x98 */ None, None, None, None, None, None, None, None,
+    /* 0xA0 */ Some("Euro"), Some("Upsilon1"), Some("minute"), Some("lessequal"), Some("fraction"), Some("infinity"), Some("florin"), Some("club"),
+    /* 0xA8 */ Some("diamond"), Some("heart"), Some("spade"), Some("arrowboth"), Some("arrowleft"), Some("arrowup"), Some("arrowright"), Some("arrowdown"),
+    /* 0xB0 */ Some("degree"), Some("plusminus"), Some("second"), Some("greaterequal"), Some("multiply"), Some("proportional"), Some("partialdiff"), Some("bullet"),
+    /* 0xB8 */ Some("divide"), Some("notequal"), Some("equivalence"), Some("approxequal"), Some("ellipsis"), Some("arrowvertex"), Some("arrowhorizex"), Some("carriagereturn"),
+    /* 0xC0 */ Some("aleph"), Some("Ifraktur"), Some("Rfraktur"), Some("weierstrass"), Some("circlemultiply"), Some("circleplus"), Some("emptyset"), Some("intersection"),
+    /* 0xC8 */ Some("union"), Some("propersuperset"), Some("reflexsuperset"), Some("notsubset"), Some("propersubset"), Some("reflexsubset"), Some("element"), Some("notelement"),
+    /* 0xD0 */ Some("angle"), Some("gradient"), Some("registerserif"), Some("copyrightserif"), Some("trademarkserif"), Some("product"), Some("radical"), Some("dotmath"),
+    /* 0xD8 */ Some("logicalnot"), Some("logicaland"), Some("logicalor"), Some("arrowdblboth"), Some("arrowdblleft"), Some("arrowdblup"), Some("arrowdblright"), Some("arrowdbldown"),
+    /* 0xE0 */ Some("lozenge"), Some("angleleft"), Some("registersans"), Some("copyrightsans"), Some("trademarksans"), Some("summation"), Some("parenlefttp"), Some("parenleftex"),
+    /* 0xE8 */ Some("parenleftbt"), Some("bracketlefttp"), Some("bracketleftex"), Some("bracketleftbt"), Some("bracelefttp"), Some("braceleftmid"), Some("braceleftbt"), Some("braceex"),
+    /* 0xF0 */ None, Some("angleright"), Some("integral"), Some("integraltp"), Some("integralex"), Some("integralbt"), Some("parenrighttp"), Some("parenrightex"),
+    /* 0xF8 */ Some("parenrightbt"), Some("bracketrighttp"), Some("bracketrightex"), Some("bracketrightbt"), Some("bracerighttp"), Some("bracerightmid"), Some("bracerightbt"), None,
+];
+
+/// Built-in encoding of the standard ZapfDingbats font, by Unicode value
+/// since its glyphs have no names in the Adobe Glyph List.
+pub const ZAPF_DINGBATS_ENCODING: [Option<char>; 256] = [
+    /* 0x00 */ None, None, None, None, None, None, None, None,
+    /* 0x08 */ None, None, None, None, None, None, None, None,
+    /* 0x10 */ None, None, None, None, None, None, None, None,
+    /* 0x18 */ None, None, None, None, None, None, None, None,
+    /* 0x20 */ Some('\u{0020}'), Some('\u{2701}'), Some('\u{2702}'), Some('\u{2703}'), Some('\u{2704}'), Some('\u{260E}'), Some('\u{2706}'), Some('\u{2707}'),
+    /* 0x28 */ Some('\u{2708}'), Some('\u{2709}'), Some('\u{261B}'), Some('\u{261E}'), Some('\u{270C}'), Some('\u{270D}'), Some('\u{270E}'), Some('\u{270F}'),
+    /* 0x30 */ Some('\u{2710}'), Some('\u{2711}'), Some('\u{2712}'), Some('\u{2713}'), Some('\u{2714}'), Some('\u{2715}'), Some('\u{2716}'), Some('\u{2717}'),
+    /* 0x38 */ Some('\u{2718}'), Some('\u{2719}'), Some('\u{271A}'), Some('\u{271B}'), Some('\u{271C}'), Some('\u{271D}'), Some('\u{271E}'), Some('\u{271F}'),
+    /* 0x40 */ Some('\u{2720}'), Some('\u{2721}'), Some('\u{2722}'), Some('\u{2723}'), Some('\u{2724}'), Some('\u{2725}'), Some('\u{2726}'), Some('\u{2727}'),
+    /* 0x48 */ Some('\u{2605}'), Some('\u{2729}'), Some('\u{272A}'), Some('\u{272B}'), Some('\u{272C}'), Some('\u{272D}'), Some('\u{272E}'), Some('\u{272F}'),
+    /* 0x50 */ Some('\u{2730}'), Some('\u{2731}'), Some('\u{2732}'), Some('\u{2733}'), Some('\u{2734}'), Some('\u{2735}'), Some('\u{2736}'), Some('\u{2737}'),
+    /* 0x58 */ Some('\u{2738}'), Some('\u{2739}'), Some('\u{273A}'), Some('\u{273B}'), Some('\u{273C}'), Some('\u{273D}'), Some('\u{273E}'), Some('\u{273F}'),
+    /* 0x60 */ Some('\u{2740}'), Some('\u{2741}'), Some('\u{2742}'), Some('\u{2743}'), Some('\u{2744}'), Some('\u{2745}'), Some('\u{2746}'), Some('\u{2747}'),
+    /* 0x68 */ Some('\u{2748}'), Some('\u{2749}'), Some('\u{274A}'), Some('\u{274B}'), Some('\u{25CF}'), Some('\u{274D}'), Some('\u{25A0}'), Some('\u{274F}'),
+    /* 0x70 */ Some('\u{2750}'), Some('\u{2751}'), Some('\u{2752}'), Some('\u{25B2}'), Some('\u{25BC}'), Some('\u{25C6}'), Some('\u{2756}'), Some('\u{25D7}'),
+    /* 0x78 */ Some('\u{2758}'), Some('\u{2759}'), Some('\u{275A}'), Some('\u{275B}'), Some('\u{275C}'), Some('\u{275D}'), Some('\u{275E}'), None,
+    /* 0x80 */ Some('\u{2768}'), Some('\u{2769}'), Some('\u{276A}'), Some('\u{276B}'), Some('\u{276C}'), Some('\u{276D}'), Some('\u{276E}'), Some('\u{276F}'),
+    /* 0x88 */ Some('\u{2770}'), Some('\u{2771}'), Some('\u{2772}'), Some('\u{2773}'), Some('\u{2774}'), Some('\u{2775}'), None, None,
+    /* 0x90 */ None, None, None, None, None, None, None, None,
+    /* 0x98 */ None, None, None, None, None, None, None, None,
+    /* 0xA0 */ None, Some('\u{2761}'), Some('\u{2762}'), Some('\u{2763}'), Some('\u{2764}'), Some('\u{2765}'), Some('\u{2766}'), Some('\u{2767}'),
+    /* 0xA8 */ Some('\u{2663}'), Some('\u{2666}'), Some('\u{2665}'), Some('\u{2660}'), Some('\u{2460}'), Some('\u{2461}'), Some('\u{2462}'), Some('\u{2463}'),
+    /* 0xB0 */ Some('\u{2464}'), Some('\u{2465}'), Some('\u{2466}'), Some('\u{2467}'), Some('\u{2468}'), Some('\u{2469}'), Some('\u{2776}'), Some('\u{2777}'),
+    /* 0xB8 */ Some('\u{2778}'), Some('\u{2779}'), Some('\u{277A}'), Some('\u{277B}'), Some('\u{277C}'), Some('\u{277D}'), Some('\u{277E}'), Some('\u{277F}'),
+    /* 0xC0 */ Some('\u{2780}'), Some('\u{2781}'), Some('\u{2782}'), Some('\u{2783}'), Some('\u{2784}'), Some('\u{2785}'), Some('\u{2786}'), Some('\u{2787}'),
+    /* 0xC8 */ Some('\u{2788}'), Some('\u{2789}'), Some('\u{278A}'), Some('\u{278B}'), Some('\u{278C}'), Some('\u{278D}'), Some('\u{278E}'), Some('\u{278F}'),
+    /* 0xD0 */ Some('\u{2790}'), Some('\u{2791}'), Some('\u{2792}'), Some('\u{2793}'), Some('\u{2794}'), Some('\u{2192}'), Some('\u{2194}'), Some('\u{2195}'),
+    /* 0xD8 */ Some('\u{2798}'), Some('\u{2799}'), Some('\u{279A}'), Some('\u{279B}'), Some('\u{279C}'), Some('\u{279D}'), Some('\u{279E}'), Some('\u{279F}'),
+    /* 0xE0 */ Some('\u{27A0}'), Some('\u{27A1}'), Some('\u{27A2}'), Some('\u{27A3}'), Some('\u{27A4}'), Some('\u{27A5}'), Some('\u{27A6}'), Some('\u{27A7}'),
+    /* 0xE8 */ Some('\u{27A8}'), Some('\u{27A9}'), Some('\u{27AA}'), Some('\u{27AB}'), Some('\u{27AC}'), Some('\u{27AD}'), Some('\u{27AE}'), Some('\u{27AF}'),
+    /* 0xF0 */ None, Some('\u{27B1}'), Some('\u{27B2}'), Some('\u{27B3}'), Some('\u{27B4}'), Some('\u{27B5}'), Some('\u{27B6}'), Some('\u{27B7}'),
+    /* 0xF8 */ Some('\u{27B8}'), Some('\u{27B9}'), Some('\u{27BA}'), Some('\u{27BB}'), Some('\u{27BC}'), Some('\u{27BD}'), Some('\u{27BE}'), None,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    fn encoding(dict: Dictionary, base_font: &str) -> FontEncoding {
+        FontEncoding::from_font(&Document::new(), &dict, FontType::Type1, base_font, None, None)
+    }
+
+    #[test]
+    fn glyph_names_to_unicode() {
+        assert_eq!(glyph_to_unicode("A").as_deref(), Some("A"));
+        assert_eq!(glyph_to_unicode("a.sc").as_deref(), Some("a"));
+        assert_eq!(glyph_to_unicode("f_f_i").as_deref(), Some("\u{FB03}"));
+        assert_eq!(glyph_to_unicode("T_h").as_deref(), Some("Th"));
+        assert_eq!(glyph_to_unicode("uni00410042").as_deref(), Some("AB"));
+        assert_eq!(glyph_to_unicode("uni0041_B.alt").as_deref(), Some("AB"));
+        assert_eq!(glyph_to_unicode("u1F600").as_deref(), Some("\u{1F600}"));
+        assert_eq!(glyph_to_unicode("uniD800"), None);
+        assert_eq!(glyph_to_unicode("g123"), None);
+    }
+
+    #[test]
+    fn glyph_list_is_sorted() {
+        assert!(GLYPH_LIST.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn differences_modify_the_base_encoding() {
+        let encoding = encoding(dictionary! {
+            "Encoding" => dictionary! {
+                "BaseEncoding" => "WinAnsiEncoding",
+                "Differences" => vec![
+                    Object::Integer(65), "Alpha".into(), "B.sc".into(),
+                    Object::Integer(128), "f_f_i".into(),
+                ],
+            },
+        }, "Helvetica");
+        assert_eq!(encoding.to_unicode(65), Some("\u{391}"));
+        assert_eq!(encoding.to_unicode(66), Some("B"));
+        assert_eq!(encoding.glyph_name(67), Some("C"));
+        assert_eq!(encoding.to_unicode(128), Some("\u{FB03}"));
+        assert_eq!(encoding.to_unicode(0x92), Some("\u{2019}"));
+    }
+
+    #[test]
+    fn symbol_and_dingbats_builtin_encodings() {
+        let symbol = encoding(Dictionary::new(), "ABCDEF+Symbol");
+        assert_eq!(symbol.to_unicode(0x61), Some("\u{3B1}"));
+        assert_eq!(symbol.to_unicode(0xA0), Some("\u{20AC}"));
+
+        let dingbats = encoding(Dictionary::new(), "ZapfDingbats");
+        assert_eq!(dingbats.glyph_name(0x21), None);
+        assert_eq!(dingbats.to_unicode(0x21), Some("\u{2701}"));
+    }
+}
@@ -0,0 +1,95 @@
+// src/engines/pdf/src/parser/ext_gstate.rs
+use lopdf::{Document, Dictionary, Object, ObjectId};
+use super::resolve;
+use crate::content::{BlendMode, LineCap, LineJoin, RenderingIntent, SoftMask, SoftMaskType};
+
+/// Parameters from an `/ExtGState` resource, applied by the `gs` operator.
+/// Entries absent from the dictionary are `None` and leave the current
+/// graphics state unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct ExtGState {
+    pub line_width: Option<f32>,
+    pub line_cap: Option<LineCap>,
+    pub line_join: Option<LineJoin>,
+    pub miter_limit: Option<f32>,
+    pub dash_pattern: Option<(Vec<f32>, f32)>,
+    pub rendering_intent: Option<RenderingIntent>,
+    pub flatness: Option<f32>,
+    /// Font as a reference to the font dictionary and a size. A direct
+    /// font dictionary is not supported and leaves this `None`.
+    pub font: Option<(ObjectId, f32)>,
+    pub stroke_alpha: Option<f32>,
+    pub fill_alpha: Option<f32>,
+    pub blend_mode: Option<BlendMode>,
+    /// `Some(None)` is an explicit `/SMask /None`, which clears the mask.
+    pub soft_mask: Option<Option<SoftMask>>,
+}
+
+impl ExtGState {
+    pub fn from_dictionary(doc: &Document, dict: &Dictionary) -> Self {
+        let number = |key: &[u8]| dict.get(key).ok()
+            .map(|o| resolve(doc, o))
+            .and_then(|o| o.as_float().ok());
+        let code = |key: &[u8]| number(key).map(|n| n as i32);
+
+        ExtGState {
+            line_width: number(b"LW"),
+            line_cap: code(b"LC").and_then(LineCap::from_code),
+            line_join: code(b"LJ").and_then(LineJoin::from_code),
+            miter_limit: number(b"ML"),
+            dash_pattern: dict.get(b"D").ok()
+                .and_then(|o| resolve(doc, o).as_array().ok())
+                .and_then(|d| Self::parse_dash(doc, d)),
+            rendering_intent: dict.get(b"RI").ok()
+                .and_then(|o| o.as_name_str().ok())
+                .map(RenderingIntent::from_name),
+            flatness: number(b"FL"),
+            font: dict.get(b"Font").ok()
+                .and_then(|o| resolve(doc, o).as_array().ok())
+                .and_then(|font| match font.as_slice() {
+                    [Object::Reference(id), size] => Some((*id, size.as_float().ok()?)),
+                    _ => None,
+                }),
+            stroke_alpha: number(b"CA"),
+            fill_alpha: number(b"ca"),
+            blend_mode: dict.get(b"BM").ok().map(|o| Self::parse_blend_mode(resolve(doc, o))),
+            soft_mask: dict.get(b"SMask").ok().map(|o| Self::parse_soft_mask(doc, o)),
+        }
+    }
+
+    fn parse_dash(doc: &Document, dash: &[Object]) -> Option<(Vec<f32>, f32)> {
+        let array = resolve(doc, dash.first()?).as_array().ok()?
+            .iter()
+            .filter_map(|n| n.as_float().ok())
+            .collect();
+        let phase = dash.get(1)?.as_float().ok()?;
+        Some((array, phase))
+    }
+
+    /// `/BM` may be an array of modes, of which the first one supported is used.
+    fn parse_blend_mode(object: &Object) -> BlendMode {
+        match object {
+            Object::Name(name) => BlendMode::from_name(&String::from_utf8_lossy(name))
+                .unwrap_or(BlendMode::Normal),
+            Object::Array(modes) => modes.iter()
+                .filter_map(|mode| mode.as_name_str().ok())
+                .find_map(BlendMode::from_name)
+                .unwrap_or(BlendMode::Normal),
+            _ => BlendMode::Normal,
+        }
+    }
+
+    fn parse_soft_mask(doc: &Document, object: &Object) -> Option<SoftMask> {
+        let dict = resolve(doc, object).as_dict().ok()?;
+        let subtype = match dict.get(b"S").and_then(Object::as_name_str).ok()? {
+            "Luminosity" => SoftMaskType::Luminosity,
+            _ => SoftMaskType::Alpha,
+        };
+        let group = dict.get(b"G").and_then(Object::as_reference).ok()?;
+        let backdrop = dict.get(b"BC").ok()
+            .and_then(|o| resolve(doc, o).as_array().ok())
+            .map(|bc| bc.iter().filter_map(|n| n.as_float().ok()).collect())
+            .unwrap_or_default();
+        Some(SoftMask { subtype, group, backdrop })
+    }
+}
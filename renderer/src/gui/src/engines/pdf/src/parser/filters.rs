@@ -0,0 +1,476 @@
+// src/engines/pdf/src/parser/filters.rs
+use std::io::Read;
+use flate2::read::ZlibDecoder;
+use lopdf::{Dictionary, Object, Stream};
+use super::PDFError;
+
+/// Filters producing compressed image formats, which are left encoded for
+/// the browser's image decoders. Browsers cannot decode `CCITTFaxDecode`
+/// or `JBIG2Decode` images, so those are not supported.
+const IMAGE_FILTERS: [&str; 2] = ["DCTDecode", "JPXDecode"];
+
+/// Entries of `/DecodeParms` used by the predictor and LZW filters.
+#[derive(Debug, Clone)]
+pub struct DecodeParams {
+    pub predictor: u32,
+    pub colors: usize,
+    pub bits_per_component: usize,
+    pub columns: usize,
+    pub early_change: bool,
+}
+
+impl Default for DecodeParams {
+    fn default() -> Self {
+        DecodeParams {
+            predictor: 1,
+            colors: 1,
+            bits_per_component: 8,
+            columns: 1,
+            early_change: true,
+        }
+    }
+}
+
+impl DecodeParams {
+    pub fn from_dictionary(dict: &Dictionary) -> Self {
+        let defaults = DecodeParams::default();
+        let integer = |key: &[u8]| dict.get(key).and_then(Object::as_i64).ok()
+            .filter(|n| *n >= 0);
+        DecodeParams {
+            predictor: integer(b"Predictor").map_or(defaults.predictor, |n| n as u32),
+            colors: integer(b"Colors").map_or(defaults.colors, |n| n as usize),
+            bits_per_component: integer(b"BitsPerComponent").map_or(defaults.bits_per_component, |n| n as usize),
+            columns: integer(b"Columns").map_or(defaults.columns, |n| n as usize),
+            early_change: integer(b"EarlyChange").map_or(defaults.early_change, |n| n != 0),
+        }
+    }
+}
+
+/// A filter name with its decode parameters.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    pub name: String,
+    pub params: DecodeParams,
+}
+
+/// Returns the filters of a stream in decoding order.
+pub fn stream_filters(stream: &Stream) -> Vec<Filter> {
+    let names: Vec<String> = match stream.dict.get(b"Filter") {
+        Ok(Object::Name(name)) => vec![String::from_utf8_lossy(name).into_owned()],
+        Ok(Object::Array(names)) => names.iter()
+            .filter_map(|name| name.as_name_str().ok().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    // `/DecodeParms` is a single dictionary or an array parallel to `/Filter`
+    let params = |index: usize| -> DecodeParams {
+        let dict = match stream.dict.get(b"DecodeParms") {
+            Ok(Object::Dictionary(dict)) if index == 0 => Some(dict),
+            Ok(Object::Array(array)) => array.get(index).and_then(|o| o.as_dict().ok()),
+            _ => None,
+        };
+        dict.map(DecodeParams::from_dictionary).unwrap_or_default()
+    };
+
+    names.into_iter()
+        .enumerate()
+        .map(|(index, name)| Filter { name, params: params(index) })
+        .collect()
+}
+
+/// Whether `apply_filters` can decode data encoded with filter `name`, or
+/// pass it on to the browser.
+pub fn is_supported_filter(name: &str) -> bool {
+    matches!(name, "FlateDecode" | "LZWDecode" | "ASCIIHexDecode" | "ASCII85Decode" | "RunLengthDecode")
+        || IMAGE_FILTERS.contains(&name)
+}
+
+/// Decodes the data of a stream by applying each of its filters.
+pub fn decode_stream(stream: &Stream) -> Result<Vec<u8>, PDFError> {
+    match apply_filters(&stream.content, &stream_filters(stream))? {
+        (data, None) => Ok(data),
+        (_, Some(filter)) => Err(PDFError::DecompressionError(format!("Unsupported filter {}", filter))),
+    }
+}
+
+/// Decodes the data of an image stream. Alongside the data, returns the
+/// name of an image compression filter that was left unapplied, if any.
+pub fn decode_image_stream(stream: &Stream) -> Result<(Vec<u8>, Option<String>), PDFError> {
+    apply_filters(&stream.content, &stream_filters(stream))
+}
+
+/// Applies `filters` in order. Decoding stops at an image compression
+/// filter, whose name is returned with the data it should be applied to.
+pub fn apply_filters(data: &[u8], filters: &[Filter]) -> Result<(Vec<u8>, Option<String>), PDFError> {
+    let mut data = data.to_vec();
+    for (index, filter) in filters.iter().enumerate() {
+        data = match filter.name.as_str() {
+            "FlateDecode" => predict(flate_decode(&data)?, &filter.params)?,
+            "LZWDecode" => predict(lzw_decode(&data, filter.params.early_change)?, &filter.params)?,
+            "ASCIIHexDecode" => ascii_hex_decode(&data),
+            "ASCII85Decode" => ascii85_decode(&data)?,
+            "RunLengthDecode" => run_length_decode(&data),
+            name if IMAGE_FILTERS.contains(&name) && index == filters.len() - 1 => {
+                return Ok((data, Some(name.to_string())));
+            },
+            other => return Err(PDFError::DecompressionError(format!("Unsupported filter {}", other))),
+        };
+    }
+    Ok((data, None))
+}
+
+fn flate_decode(data: &[u8]) -> Result<Vec<u8>, PDFError> {
+    let mut decoder = ZlibDecoder::new(data);
+    let mut decompressed = Vec::new();
+    match decoder.read_to_end(&mut decompressed) {
+        Ok(_) => Ok(decompressed),
+        // Keep whatever was recovered from truncated or corrupt streams
+        Err(_) if !decompressed.is_empty() => Ok(decompressed),
+        Err(e) => Err(PDFError::DecompressionError(e.to_string())),
+    }
+}
+
+fn lzw_decode(data: &[u8], early_change: bool) -> Result<Vec<u8>, PDFError> {
+    const CLEAR_TABLE: usize = 256;
+    const END_OF_DATA: usize = 257;
+
+    let initial_table = || -> Vec<Vec<u8>> {
+        let mut table: Vec<Vec<u8>> = (0..=255u8).map(|b| vec![b]).collect();
+        // Placeholders for the two control codes
+        table.push(Vec::new());
+        table.push(Vec::new());
+        table
+    };
+
+    let mut table = initial_table();
+    let mut output = Vec::new();
+    let mut code_length = 9;
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    let mut previous: Option<usize> = None;
+
+    for &byte in data {
+        bits = bits << 8 | u32::from(byte);
+        bit_count += 8;
+
+        while bit_count >= code_length {
+            bit_count -= code_length;
+            let code = (bits >> bit_count) as usize & ((1 << code_length) - 1);
+            bits &= (1 << bit_count) - 1;
+
+            match code {
+                CLEAR_TABLE => {
+                    table = initial_table();
+                    code_length = 9;
+                    previous = None;
+                },
+                END_OF_DATA => return Ok(output),
+                _ => {
+                    let entry = match previous {
+                        _ if code < table.len() => table[code].clone(),
+                        // The code being defined by this very step
+                        Some(previous) if code == table.len() => {
+                            let mut entry = table[previous].clone();
+                            entry.push(entry[0]);
+                            entry
+                        },
+                        _ => return Err(PDFError::DecompressionError(format!("Invalid LZW code {}", code))),
+                    };
+                    output.extend_from_slice(&entry);
+
+                    if let Some(previous) = previous {
+                        if table.len() < 4096 {
+                            let mut new_entry = table[previous].clone();
+                            new_entry.push(entry[0]);
+                            table.push(new_entry);
+                        }
+                    }
+                    previous = Some(code);
+
+                    if table.len() + usize::from(early_change) >= 1 << code_length && code_length < 12 {
+                        code_length += 1;
+                    }
+                },
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+pub fn ascii_hex_decode(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut high: Option<u8> = None;
+
+    for &b in data {
+        if b == b'>' {
+            break;
+        }
+        let value = match b {
+            b'0'..=b'9' => b - b'0',
+            b'a'..=b'f' => b - b'a' + 10,
+            b'A'..=b'F' => b - b'A' + 10,
+            _ => continue,
+        };
+        match high.take() {
+            Some(h) => output.push(h << 4 | value),
+            None => high = Some(value),
+        }
+    }
+
+    // An odd number of digits behaves as if followed by a zero
+    if let Some(h) = high {
+        output.push(h << 4);
+    }
+    output
+}
+
+fn ascii85_decode(data: &[u8]) -> Result<Vec<u8>, PDFError> {
+    let mut output = Vec::new();
+    let mut group = [0u8; 5];
+    let mut count = 0;
+
+    for &b in data {
+        match b {
+            b'~' => break,
+            b'z' if count == 0 => output.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group[count] = b - b'!';
+                count += 1;
+                if count == 5 {
+                    let value = group.iter().fold(0u64, |acc, &digit| acc * 85 + u64::from(digit));
+                    output.extend_from_slice(&(value as u32).to_be_bytes());
+                    count = 0;
+                }
+            },
+            b if b.is_ascii_whitespace() || b == 0 => {},
+            other => return Err(PDFError::DecompressionError(format!(
+                "Invalid ASCII85 character {:?}", other as char
+            ))),
+        }
+    }
+
+    // A final partial group is padded with the highest digit
+    if count > 1 {
+        for digit in group.iter_mut().skip(count) {
+            *digit = 84;
+        }
+        let value = group.iter().fold(0u64, |acc, &digit| acc * 85 + u64::from(digit));
+        output.extend_from_slice(&(value as u32).to_be_bytes()[..count - 1]);
+    }
+    Ok(output)
+}
+
+fn run_length_decode(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut position = 0;
+
+    while let Some(&length) = data.get(position) {
+        position += 1;
+        match length {
+            128 => break,
+            0..=127 => {
+                let end = (position + length as usize + 1).min(data.len());
+                output.extend_from_slice(&data[position..end]);
+                position = end;
+            },
+            _ => {
+                if let Some(&b) = data.get(position) {
+                    output.extend(std::iter::repeat_n(b, 257 - length as usize));
+                }
+                position += 1;
+            },
+        }
+    }
+    output
+}
+
+/// Reverses the TIFF or PNG predictor applied before compression.
+fn predict(data: Vec<u8>, params: &DecodeParams) -> Result<Vec<u8>, PDFError> {
+    let bits_per_pixel = params.colors * params.bits_per_component;
+    let row_length = (params.columns * bits_per_pixel).div_ceil(8);
+    let bytes_per_pixel = bits_per_pixel.div_ceil(8).max(1);
+
+    match params.predictor {
+        2 if params.bits_per_component == 8 && row_length > 0 => {
+            let mut data = data;
+            for row in data.chunks_mut(row_length) {
+                for i in bytes_per_pixel..row.len() {
+                    row[i] = row[i].wrapping_add(row[i - bytes_per_pixel]);
+                }
+            }
+            Ok(data)
+        },
+        10..=15 if row_length > 0 => {
+            // Each row is preceded by a byte selecting its PNG filter type
+            let mut output = Vec::with_capacity(data.len());
+            let mut previous = vec![0u8; row_length];
+            for chunk in data.chunks(row_length + 1) {
+                let (filter_type, encoded) = chunk.split_first().unwrap_or((&0, &[]));
+                let mut row = encoded.to_vec();
+                row.resize(row_length, 0);
+
+                for i in 0..row_length {
+                    let left = if i >= bytes_per_pixel { row[i - bytes_per_pixel] } else { 0 };
+                    let up = previous[i];
+                    let up_left = if i >= bytes_per_pixel { previous[i - bytes_per_pixel] } else { 0 };
+                    let predicted = match filter_type {
+                        0 => 0,
+                        1 => left,
+                        2 => up,
+                        3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                        4 => paeth(left, up, up_left),
+                        other => return Err(PDFError::DecompressionError(format!(
+                            "Invalid PNG predictor {}", other
+                        ))),
+                    };
+                    row[i] = row[i].wrapping_add(predicted);
+                }
+
+                output.extend_from_slice(&row);
+                previous = row;
+            }
+            Ok(output)
+        },
+        _ => Ok(data),
+    }
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = i16::from(left) + i16::from(up) - i16::from(up_left);
+    let distance = |value: u8| (estimate - i16::from(value)).abs();
+    if distance(left) <= distance(up) && distance(left) <= distance(up_left) {
+        left
+    } else if distance(up) <= distance(up_left) {
+        up
+    } else {
+        up_left
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Encodes `data` with LZW, widening codes one step early like the
+    /// decoder expects when `early_change` is set.
+    fn lzw_encode(data: &[u8], early_change: bool) -> Vec<u8> {
+        let mut table: HashMap<Vec<u8>, usize> = (0..=255u8).map(|b| (vec![b], usize::from(b))).collect();
+        let mut code_length = 9;
+        let mut output = Vec::new();
+        let mut bits: u64 = 0;
+        let mut bit_count = 0;
+        let mut write = |code: usize, code_length: usize| {
+            bits = bits << code_length | code as u64;
+            bit_count += code_length;
+            while bit_count >= 8 {
+                bit_count -= 8;
+                output.push((bits >> bit_count) as u8);
+            }
+        };
+
+        write(256, code_length);
+        let mut current: Vec<u8> = Vec::new();
+        for &b in data {
+            let mut extended = current.clone();
+            extended.push(b);
+            if current.is_empty() || table.contains_key(&extended) {
+                current = extended;
+                continue;
+            }
+            write(table[&current], code_length);
+            // The decoder defines each entry one code later
+            table.insert(extended, table.len() + 2);
+            if table.len() + 1 + usize::from(early_change) >= 1 << code_length && code_length < 12 {
+                code_length += 1;
+            }
+            current = vec![b];
+        }
+        write(table[&current], code_length);
+        write(257, code_length);
+        write(0, 7);
+        output
+    }
+
+    fn filter(name: &str, params: DecodeParams) -> Filter {
+        Filter { name: name.to_string(), params }
+    }
+
+    #[test]
+    fn lzw_example_from_the_specification() {
+        let data = [0x80, 0x0B, 0x60, 0x50, 0x22, 0x0C, 0x0C, 0x85, 0x01];
+        assert_eq!(lzw_decode(&data, true).unwrap(), b"-----A---B");
+    }
+
+    #[test]
+    fn lzw_code_length_changes() {
+        // Enough distinct sequences to widen codes to 10 and 11 bits
+        let mut seed = 1u32;
+        let data: Vec<u8> = (0..3000).map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) as u8 % 16
+        }).collect();
+        for early_change in [true, false] {
+            let encoded = lzw_encode(&data, early_change);
+            assert_eq!(lzw_decode(&encoded, early_change).unwrap(), data, "early change {}", early_change);
+        }
+    }
+
+    #[test]
+    fn ascii85_groups_and_terminator() {
+        assert_eq!(ascii85_decode(b"9jqo^zBOu!rD]j7BEbo80~>").unwrap(), b"Man \0\0\0\0hello world!");
+        // Data after `~>` is ignored, and whitespace anywhere
+        assert_eq!(ascii85_decode(b"9jq\no^~>garbage").unwrap(), b"Man ");
+        // A final partial group
+        assert_eq!(ascii85_decode(b"9jqo~>").unwrap(), b"Man");
+        assert!(ascii85_decode(b"9jqo^{").is_err());
+    }
+
+    #[test]
+    fn ascii_hex_odd_digits() {
+        assert_eq!(ascii_hex_decode(b"48 65\n6c7>ff"), b"Hel\x70");
+    }
+
+    #[test]
+    fn run_length_runs_and_literals() {
+        assert_eq!(run_length_decode(&[2, b'a', b'b', b'c', 254, b'x', 128, b'z']), b"abcxxx");
+    }
+
+    #[test]
+    fn png_predictors() {
+        let params = DecodeParams { predictor: 12, colors: 1, bits_per_component: 8, columns: 3, early_change: true };
+        // Sub, Up, Average and Paeth rows
+        let data = vec![
+            1, 10, 5, 5,
+            2, 1, 1, 1,
+            3, 2, 2, 2,
+            4, 1, 1, 1,
+        ];
+        let decoded = predict(data, &params).unwrap();
+        assert_eq!(decoded, [10, 15, 20, 11, 16, 21, 7, 13, 19, 8, 14, 20]);
+
+        let invalid = predict(vec![5, 0, 0, 0], &params);
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn tiff_predictor() {
+        let params = DecodeParams { predictor: 2, colors: 3, bits_per_component: 8, columns: 2, early_change: true };
+        let decoded = predict(vec![10, 20, 30, 1, 2, 3], &params).unwrap();
+        assert_eq!(decoded, [10, 20, 30, 11, 22, 33]);
+    }
+
+    #[test]
+    fn filters_stop_at_image_compression() {
+        let filters = [filter("ASCIIHexDecode", DecodeParams::default()), filter("DCTDecode", DecodeParams::default())];
+        let (data, unapplied) = apply_filters(b"FFD8>", &filters).unwrap();
+        assert_eq!(data, [0xFF, 0xD8]);
+        assert_eq!(unapplied.as_deref(), Some("DCTDecode"));
+
+        let fax = [filter("CCITTFaxDecode", DecodeParams::default())];
+        assert!(apply_filters(b"", &fax).is_err());
+        assert!(!is_supported_filter("JBIG2Decode"));
+    }
+}
@@ -1,23 +1,580 @@
-use lopdf::{Document, Dictionary};
-use super::{PDFError, FontType};
+use std::collections::HashMap;
+use lopdf::{Document, Dictionary, Object, ObjectId};
+use super::{PDFError, FontType, resolve};
+use super::cmap::CMap;
+use super::encoding::FontEncoding;
+use super::filters::decode_stream;
+use super::font_program::FontProgram;
+use crate::content::Matrix;
+
+/// Width assumed for glyphs of fonts that carry no `/Widths` array.
+const DEFAULT_WIDTH: f32 = 500.0;
+
+/// `/DW` of CIDFonts that omit it.
+const DEFAULT_CID_WIDTH: f32 = 1000.0;
+
+/// `/DW2` of CIDFonts that omit it: the vertical origin's y and the
+/// vertical displacement.
+const DEFAULT_CID_VERTICAL: [f32; 2] = [880.0, -1000.0];
 
 pub struct PDFFont {
+    /// The `/BaseFont` name, including any subset tag such as `ABCDEF+`.
     pub name: String,
     pub font_type: FontType,
-    data: Vec<u8>,
+    pub descriptor: Option<FontDescriptor>,
+    /// The embedded font program; standard 14 fonts and fonts the viewer
+    /// should substitute carry none.
+    pub program: Option<FontProgram>,
+    /// Code to glyph name mapping of simple fonts; `None` for Type 0 fonts.
+    encoding: Option<FontEncoding>,
+    /// The `/ToUnicode` CMap, which takes precedence over the encoding for
+    /// extracted text.
+    unicode_map: Option<CMap>,
+    /// The CMap of a Type 0 font's `/Encoding`, mapping codes to CIDs.
+    cmap: Option<CMap>,
+    cid_font: Option<CIDFont>,
+    first_char: u32,
+    widths: Vec<f32>,
+    missing_width: f32,
+    type3: Option<Type3Font>,
+}
+
+/// Metrics and style of a font from its `/FontDescriptor`, in thousandths
+/// of text space.
+#[derive(Debug, Clone, Default)]
+pub struct FontDescriptor {
+    pub font_name: String,
+    pub font_family: Option<String>,
+    pub flags: FontFlags,
+    /// `[llx lly urx ury]` enclosing all glyphs.
+    pub font_bbox: [f32; 4],
+    /// Degrees counterclockwise from vertical.
+    pub italic_angle: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    pub leading: f32,
+    pub cap_height: f32,
+    pub x_height: f32,
+    pub stem_v: f32,
+    pub stem_h: f32,
+    pub avg_width: f32,
+    pub max_width: f32,
+    pub missing_width: f32,
+}
+
+impl FontDescriptor {
+    fn from_dictionary(doc: &Document, dict: &Dictionary) -> Self {
+        let number = |key: &[u8]| dict.get(key).ok()
+            .and_then(|o| resolve(doc, o).as_float().ok())
+            .unwrap_or(0.0);
+        let name = |key: &[u8]| dict.get(key).ok()
+            .and_then(|o| match resolve(doc, o) {
+                Object::Name(name) | Object::String(name, _) => Some(String::from_utf8_lossy(name).into_owned()),
+                _ => None,
+            });
+        let bbox: Vec<f32> = dict.get(b"FontBBox").ok()
+            .and_then(|o| resolve(doc, o).as_array().ok())
+            .map(|a| a.iter().filter_map(|n| resolve(doc, n).as_float().ok()).collect())
+            .unwrap_or_default();
+
+        FontDescriptor {
+            font_name: name(b"FontName").unwrap_or_default(),
+            font_family: name(b"FontFamily"),
+            flags: FontFlags(number(b"Flags") as u32),
+            font_bbox: match bbox[..] {
+                [llx, lly, urx, ury] => [llx, lly, urx, ury],
+                _ => [0.0; 4],
+            },
+            italic_angle: number(b"ItalicAngle"),
+            ascent: number(b"Ascent"),
+            descent: number(b"Descent"),
+            leading: number(b"Leading"),
+            cap_height: number(b"CapHeight"),
+            x_height: number(b"XHeight"),
+            stem_v: number(b"StemV"),
+            stem_h: number(b"StemH"),
+            avg_width: number(b"AvgWidth"),
+            max_width: number(b"MaxWidth"),
+            missing_width: number(b"MissingWidth"),
+        }
+    }
+}
+
+/// The `/Flags` entry of a font descriptor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FontFlags(pub u32);
+
+impl FontFlags {
+    pub const FIXED_PITCH: u32 = 1;
+    pub const SERIF: u32 = 1 << 1;
+    /// Glyphs outside the standard Latin character set; the font's built-in
+    /// encoding applies.
+    pub const SYMBOLIC: u32 = 1 << 2;
+    pub const SCRIPT: u32 = 1 << 3;
+    pub const NONSYMBOLIC: u32 = 1 << 5;
+    pub const ITALIC: u32 = 1 << 6;
+    pub const ALL_CAP: u32 = 1 << 16;
+    pub const SMALL_CAP: u32 = 1 << 17;
+    pub const FORCE_BOLD: u32 = 1 << 18;
+
+    pub fn contains(&self, flag: u32) -> bool {
+        self.0 & flag != 0
+    }
+}
+
+/// Glyph procedures of a Type 3 font, which draws each glyph with a
+/// content stream in its own glyph space.
+pub struct Type3Font {
+    /// Maps glyph space to text space.
+    pub font_matrix: Matrix,
+    /// Resources of the glyph procedures; when absent they use those of the
+    /// content stream showing the text.
+    pub resources: Option<Dictionary>,
+    char_procs: HashMap<String, ObjectId>,
+}
+
+impl Type3Font {
+    fn from_dictionary(doc: &Document, dict: &Dictionary) -> Self {
+        let matrix: Vec<f32> = dict.get(b"FontMatrix").ok()
+            .and_then(|o| resolve(doc, o).as_array().ok())
+            .map(|a| a.iter().filter_map(|n| n.as_float().ok()).collect())
+            .unwrap_or_default();
+        let font_matrix = match matrix[..] {
+            [a, b, c, d, e, f] => Matrix::new(a, b, c, d, e, f),
+            _ => Matrix::new(0.001, 0.0, 0.0, 0.001, 0.0, 0.0),
+        };
+
+        let char_procs = dict.get(b"CharProcs").ok()
+            .and_then(|o| resolve(doc, o).as_dict().ok())
+            .map(|procs| procs.iter()
+                .filter_map(|(name, o)| Some((String::from_utf8_lossy(name).into_owned(), o.as_reference().ok()?)))
+                .collect())
+            .unwrap_or_default();
+
+        let resources = dict.get(b"Resources").ok()
+            .and_then(|o| resolve(doc, o).as_dict().ok())
+            .cloned();
+
+        Type3Font { font_matrix, resources, char_procs }
+    }
+
+    /// The glyph procedure drawing the glyph `name`.
+    pub fn char_proc(&self, name: &str) -> Option<ObjectId> {
+        self.char_procs.get(name).copied()
+    }
+}
+
+/// The descendant CIDFont of a Type 0 font, which selects glyphs and
+/// metrics by CID.
+pub struct CIDFont {
+    /// `CIDFontType0` for CFF outlines or `CIDFontType2` for TrueType.
+    pub font_type: FontType,
+    default_width: f32,
+    widths: CidMetrics<1>,
+    default_vertical: [f32; 2],
+    /// Vertical displacement and origin of each CID, from `/W2`.
+    vertical_metrics: CidMetrics<3>,
+    /// `/CIDToGIDMap` of a `CIDFontType2`; `None` when CIDs are glyph
+    /// indices.
+    cid_to_gid: Option<Vec<u16>>,
+}
+
+/// Per-CID metrics from a `/W` or `/W2` array, which lists either a first
+/// CID followed by an array of values for consecutive CIDs, or a CID range
+/// sharing the same values.
+#[derive(Default)]
+struct CidMetrics<const N: usize> {
+    individual: HashMap<u32, [f32; N]>,
+    ranges: Vec<(u32, u32, [f32; N])>,
+}
+
+impl<const N: usize> CidMetrics<N> {
+    fn from_array(doc: &Document, array: &[Object]) -> Self {
+        let mut metrics = CidMetrics { individual: HashMap::new(), ranges: Vec::new() };
+        let number = |o: &Object| resolve(doc, o).as_float().ok();
+        let mut items = array.iter();
+
+        while let Some(first) = items.next().and_then(|o| resolve(doc, o).as_i64().ok()) {
+            let first = first.max(0) as u32;
+            match items.next().map(|o| resolve(doc, o)) {
+                Some(Object::Array(values)) => {
+                    for (cid, chunk) in (first..).zip(values.chunks_exact(N)) {
+                        let mut entry = [0.0; N];
+                        for (value, o) in entry.iter_mut().zip(chunk) {
+                            *value = number(o).unwrap_or(0.0);
+                        }
+                        metrics.individual.insert(cid, entry);
+                    }
+                },
+                Some(last) => {
+                    let Ok(last) = last.as_i64() else { break };
+                    let mut entry = [0.0; N];
+                    for value in entry.iter_mut() {
+                        *value = items.next().and_then(number).unwrap_or(0.0);
+                    }
+                    metrics.ranges.push((first, last.max(0) as u32, entry));
+                },
+                None => break,
+            }
+        }
+        metrics
+    }
+
+    fn get(&self, cid: u32) -> Option<[f32; N]> {
+        self.individual.get(&cid).copied().or_else(|| {
+            self.ranges.iter()
+                .find(|(first, last, _)| (*first..=*last).contains(&cid))
+                .map(|(_, _, entry)| *entry)
+        })
+    }
+}
+
+impl CIDFont {
+    fn from_dictionary(doc: &Document, dict: &Dictionary) -> Self {
+        let font_type = dict.get(b"Subtype").and_then(Object::as_name_str).ok()
+            .and_then(FontType::from_name)
+            .filter(|font_type| *font_type == FontType::CIDFontType2)
+            .unwrap_or(FontType::CIDFontType0);
+        let array = |key: &[u8]| dict.get(key).ok()
+            .and_then(|o| resolve(doc, o).as_array().ok())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let default_vertical = match array(b"DW2") {
+            [vy, w1y] => [
+                resolve(doc, vy).as_float().unwrap_or(DEFAULT_CID_VERTICAL[0]),
+                resolve(doc, w1y).as_float().unwrap_or(DEFAULT_CID_VERTICAL[1]),
+            ],
+            _ => DEFAULT_CID_VERTICAL,
+        };
+
+        // Only TrueType-based CIDFonts map CIDs to glyphs; `/Identity` and a
+        // missing map both leave CIDs as glyph indices
+        let cid_to_gid = match font_type {
+            FontType::CIDFontType2 => dict.get(b"CIDToGIDMap").ok()
+                .and_then(|o| resolve(doc, o).as_stream().ok())
+                .and_then(|stream| decode_stream(stream).ok())
+                .map(|data| data.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect()),
+            _ => None,
+        };
+
+        CIDFont {
+            font_type,
+            default_width: dict.get(b"DW").ok()
+                .and_then(|o| resolve(doc, o).as_float().ok())
+                .unwrap_or(DEFAULT_CID_WIDTH),
+            widths: CidMetrics::from_array(doc, array(b"W")),
+            default_vertical,
+            vertical_metrics: CidMetrics::from_array(doc, array(b"W2")),
+            cid_to_gid,
+        }
+    }
+
+    /// Horizontal advance of `cid` in thousandths of text space.
+    pub fn width(&self, cid: u32) -> f32 {
+        self.widths.get(cid).map_or(self.default_width, |[width]| width)
+    }
+
+    /// Vertical displacement of `cid` and the position of its vertical
+    /// origin relative to the horizontal one, as `[w1y, vx, vy]` in
+    /// thousandths of text space.
+    pub fn vertical_metrics(&self, cid: u32) -> [f32; 3] {
+        self.vertical_metrics.get(cid).unwrap_or_else(|| {
+            let [vy, w1y] = self.default_vertical;
+            [w1y, self.width(cid) / 2.0, vy]
+        })
+    }
+
+    /// Index of the glyph drawing `cid` in the embedded font program.
+    pub fn glyph_id(&self, cid: u32) -> u32 {
+        match &self.cid_to_gid {
+            Some(map) => map.get(cid as usize).map_or(0, |&gid| u32::from(gid)),
+            None => cid,
+        }
+    }
 }
 
 impl PDFFont {
     pub fn from_dictionary(doc: &Document, dict: &Dictionary) -> Result<Self, PDFError> {
-        // Basic implementation for now
+        let font_type = match dict.get(b"Subtype").and_then(Object::as_name_str) {
+            Ok(subtype) => FontType::from_name(subtype)
+                .ok_or_else(|| PDFError::FontError(format!("Unknown font subtype {}", subtype)))?,
+            // Some producers omit the subtype of simple fonts
+            Err(_) => FontType::Type1,
+        };
+        let name = dict.get(b"BaseFont").ok()
+            .and_then(|o| resolve(doc, o).as_name_str().ok())
+            .unwrap_or("Default")
+            .to_string();
+
+        let integer = |key: &[u8]| dict.get(key).ok()
+            .and_then(|o| resolve(doc, o).as_i64().ok());
+        let first_char = integer(b"FirstChar").unwrap_or(0).max(0) as u32;
+        let mut widths: Vec<f32> = match dict.get_deref(b"Widths", doc).and_then(Object::as_array) {
+            Ok(widths) => widths.iter()
+                .map(|w| resolve(doc, w).as_float().unwrap_or(0.0))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        // Codes beyond `/LastChar` use the missing width
+        if let Some(last_char) = integer(b"LastChar") {
+            widths.truncate((last_char + 1 - i64::from(first_char)).max(0) as usize);
+        }
+
+        let descendant = match font_type {
+            FontType::Type0 => dict.get(b"DescendantFonts").ok()
+                .and_then(|o| resolve(doc, o).as_array().ok())
+                .and_then(|fonts| fonts.first())
+                .and_then(|o| resolve(doc, o).as_dict().ok()),
+            _ => None,
+        };
+        // Type 0 fonts keep their descriptor in the descendant CIDFont
+        let descriptor_dict = descendant.unwrap_or(dict)
+            .get(b"FontDescriptor").ok()
+            .and_then(|o| resolve(doc, o).as_dict().ok());
+        let descriptor = descriptor_dict.map(|d| FontDescriptor::from_dictionary(doc, d));
+
+        // A program that fails to decode leaves the font to be substituted
+        let program = descriptor_dict
+            .and_then(|d| FontProgram::from_descriptor(doc, d))
+            .and_then(Result::ok);
+
+        let encoding = match font_type {
+            FontType::Type0 => None,
+            _ => Some(FontEncoding::from_font(doc, dict, font_type, &name, descriptor.as_ref(), program.as_ref())),
+        };
+
+        // A missing or unreadable encoding reads two-byte codes as CIDs,
+        // which is right for the common Identity-H
+        let cmap = match font_type {
+            FontType::Type0 => dict.get(b"Encoding").ok()
+                .and_then(|o| CMap::load(doc, o))
+                .or_else(|| Some(CMap::predefined("Identity-H"))),
+            _ => None,
+        };
+        let cid_font = descendant.map(|descendant| CIDFont::from_dictionary(doc, descendant));
+
+        let unicode_map = dict.get(b"ToUnicode").ok()
+            .and_then(|o| resolve(doc, o).as_stream().ok())
+            .and_then(|stream| decode_stream(stream).ok())
+            .map(|data| CMap::parse(&data));
+
+        let type3 = match font_type {
+            FontType::Type3 => Some(Type3Font::from_dictionary(doc, dict)),
+            _ => None,
+        };
+
         Ok(PDFFont {
-            name: "Default".to_string(),
-            font_type: FontType::Type1,
-            data: Vec::new(),
+            name,
+            font_type,
+            first_char,
+            widths,
+            missing_width: descriptor.as_ref().map_or(0.0, |d| d.missing_width),
+            descriptor,
+            program,
+            encoding,
+            unicode_map,
+            cmap,
+            cid_font,
+            type3,
         })
     }
 
+    /// Width of the glyph for `code` as a fraction of the font size.
+    pub fn glyph_width(&self, code: u32) -> f32 {
+        if let Some(cid_font) = &self.cid_font {
+            return cid_font.width(self.cid(code)) / 1000.0;
+        }
+        if self.widths.is_empty() {
+            return DEFAULT_WIDTH / 1000.0;
+        }
+        let width = code.checked_sub(self.first_char)
+            .and_then(|index| self.widths.get(index as usize))
+            .copied()
+            .unwrap_or(self.missing_width);
+        // Type 3 widths are in glyph space rather than thousandths of text space
+        match &self.type3 {
+            Some(type3) => width * type3.font_matrix.a,
+            None => width / 1000.0,
+        }
+    }
+
+    /// Name of the glyph selected by `code` in a simple font.
+    pub fn glyph_name(&self, code: u32) -> Option<&str> {
+        self.encoding.as_ref()?.glyph_name(code)
+    }
+
+    /// Reads the character code at the start of `bytes`, returning it with
+    /// its length. Simple fonts use one byte per code; Type 0 fonts follow
+    /// the codespace of their CMap, falling back to that of the
+    /// `/ToUnicode` CMap and then to two bytes.
+    pub fn next_code(&self, bytes: &[u8]) -> (u32, usize) {
+        if self.font_type != FontType::Type0 {
+            return (bytes.first().copied().map_or(0, u32::from), bytes.len().min(1));
+        }
+        match [&self.cmap, &self.unicode_map].into_iter().flatten().find(|cmap| cmap.has_codespace()) {
+            Some(cmap) => cmap.next_code(bytes),
+            None => {
+                let length = bytes.len().min(2);
+                (bytes[..length].iter().fold(0, |code, &b| code << 8 | u32::from(b)), length)
+            },
+        }
+    }
+
+    /// The CID selected by `code` in a Type 0 font; unmapped codes select
+    /// CID 0, the missing glyph.
+    pub fn cid(&self, code: u32) -> u32 {
+        self.cmap.as_ref().and_then(|cmap| cmap.to_cid(code)).unwrap_or(0)
+    }
+
+    /// Index of the glyph for `code` in the embedded program of a Type 0
+    /// font. Simple fonts select glyphs by name instead.
+    pub fn glyph_id(&self, code: u32) -> Option<u32> {
+        Some(self.cid_font.as_ref()?.glyph_id(self.cid(code)))
+    }
+
+    /// Whether glyphs are laid out top to bottom, as selected by a Type 0
+    /// font's CMap.
+    pub fn is_vertical(&self) -> bool {
+        self.cmap.as_ref().is_some_and(CMap::is_vertical)
+    }
+
+    /// Name of the predefined CMap of a Type 0 font whose CIDs are not
+    /// available, leaving its glyphs at CID 0.
+    pub fn unavailable_cmap(&self) -> Option<&str> {
+        self.cmap.as_ref()?.unavailable()
+    }
+
+    /// For vertical fonts, the glyph's vertical displacement and the
+    /// position of its vertical origin relative to the horizontal one, as
+    /// `[w1y, vx, vy]` fractions of the font size.
+    pub fn vertical_metrics(&self, code: u32) -> Option<[f32; 3]> {
+        if !self.is_vertical() {
+            return None;
+        }
+        let metrics = self.cid_font.as_ref()?.vertical_metrics(self.cid(code));
+        Some(metrics.map(|value| value / 1000.0))
+    }
+
+    /// The text `code` stands for, from the `/ToUnicode` CMap or else the
+    /// font's encoding. Type 0 fonts only know the text of codes when their
+    /// CMap is Unicode-based.
+    pub fn to_unicode(&self, code: u32) -> Option<String> {
+        self.unicode_map.as_ref()
+            .and_then(|cmap| cmap.to_unicode(code))
+            .or_else(|| match &self.encoding {
+                Some(encoding) => encoding.to_unicode(code).map(String::from),
+                None => self.cmap.as_ref()?.to_unicode(code),
+            })
+    }
+
+    pub fn type3(&self) -> Option<&Type3Font> {
+        self.type3.as_ref()
+    }
+
+    pub fn cid_font(&self) -> Option<&CIDFont> {
+        self.cid_font.as_ref()
+    }
+
     pub fn get_font_data(&self) -> Result<&[u8], PDFError> {
-        Ok(&self.data)
+        Ok(self.program.as_ref().map_or(&[], |program| program.data.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    fn integers(values: &[i64]) -> Vec<Object> {
+        values.iter().map(|&n| Object::Integer(n)).collect()
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn cid_widths_from_lists_and_ranges() {
+        let mut w = integers(&[1]);
+        w.push(Object::Array(integers(&[500, 600])));
+        w.extend(integers(&[10, 20, 700]));
+        let font = CIDFont::from_dictionary(&Document::new(), &dictionary! {
+            "Subtype" => "CIDFontType0",
+            "DW" => 800,
+            "W" => w,
+        });
+        assert_eq!(font.width(1), 500.0);
+        assert_eq!(font.width(2), 600.0);
+        assert_eq!(font.width(15), 700.0);
+        assert_eq!(font.width(3), 800.0);
+        assert_eq!(font.width(21), 800.0);
+        assert_eq!(font.glyph_id(42), 42);
+    }
+
+    #[test]
+    fn cid_vertical_metrics() {
+        let mut w2 = integers(&[5, 5, -900, 250, 880]);
+        w2.push(Object::Integer(7));
+        w2.push(Object::Array(integers(&[-800, 300, 700])));
+        let font = CIDFont::from_dictionary(&Document::new(), &dictionary! {
+            "Subtype" => "CIDFontType0",
+            "W" => vec![Object::Integer(9), Object::Array(integers(&[600]))],
+            "DW2" => integers(&[900, -1100]),
+            "W2" => w2,
+        });
+        assert_eq!(font.vertical_metrics(5), [-900.0, 250.0, 880.0]);
+        assert_eq!(font.vertical_metrics(7), [-800.0, 300.0, 700.0]);
+        assert_eq!(font.vertical_metrics(9), [-1100.0, 300.0, 900.0]);
+    }
+
+    #[test]
+    fn cid_to_gid_map_of_truetype_cidfonts() {
+        let mut doc = Document::new();
+        let map = doc.add_object(Stream::new(Dictionary::new(), vec![0, 0, 0, 7, 1, 2]));
+        let font = CIDFont::from_dictionary(&doc, &dictionary! {
+            "Subtype" => "CIDFontType2",
+            "CIDToGIDMap" => Object::Reference(map),
+        });
+        assert_eq!(font.glyph_id(1), 7);
+        assert_eq!(font.glyph_id(2), 0x0102);
+        assert_eq!(font.glyph_id(3), 0);
+
+        let identity = CIDFont::from_dictionary(&doc, &dictionary! {
+            "Subtype" => "CIDFontType2",
+            "CIDToGIDMap" => "Identity",
+        });
+        assert_eq!(identity.glyph_id(3), 3);
+    }
+
+    #[test]
+    fn type0_font_reads_codes_through_its_cmap() {
+        let descendant = dictionary! {
+            "Subtype" => "CIDFontType0",
+            "BaseFont" => "Test",
+            "W" => vec![Object::Integer(0x0102), Object::Array(integers(&[250]))],
+        };
+        let font = PDFFont::from_dictionary(&Document::new(), &dictionary! {
+            "Subtype" => "Type0",
+            "BaseFont" => "Test",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![Object::Dictionary(descendant)],
+        }).unwrap();
+        assert_eq!(font.next_code(b"\x01\x02\x03"), (0x0102, 2));
+        assert_eq!(font.glyph_width(0x0102), 0.25);
+        assert_eq!(font.glyph_width(0x0103), 1.0);
+        assert!(!font.is_vertical());
+        assert_eq!(font.unavailable_cmap(), None);
+    }
+
+    #[test]
+    fn simple_font_widths_stop_at_last_char() {
+        let font = PDFFont::from_dictionary(&Document::new(), &dictionary! {
+            "Subtype" => "TrueType",
+            "BaseFont" => "Test",
+            "FirstChar" => 32,
+            "LastChar" => 33,
+            "Widths" => integers(&[250, 300, 400]),
+        }).unwrap();
+        assert_eq!(font.next_code(b"AB"), (0x41, 1));
+        assert_eq!(font.glyph_width(32), 0.25);
+        assert_eq!(font.glyph_width(33), 0.3);
+        assert_eq!(font.glyph_width(34), 0.0);
+    }
+}
@@ -0,0 +1,227 @@
+// src/engines/pdf/src/parser/font_program.rs
+use lopdf::{Document, Dictionary, Object, Stream};
+use super::{PDFError, resolve};
+use super::filters::{ascii_hex_decode, decode_stream};
+
+/// Number of `0` digits before the `cleartomark` ending a Type 1 program.
+const TYPE1_TRAILER_ZEROS: usize = 512;
+
+/// Container format of an embedded font program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontFormat {
+    /// PostScript Type 1, as cleartext followed by the binary eexec part.
+    Type1,
+    TrueType,
+    /// Bare CFF data of a simple font.
+    Type1C,
+    /// Bare CFF data of a CID-keyed font.
+    CIDFontType0C,
+    /// An OpenType wrapper around TrueType or CFF outlines.
+    OpenType,
+}
+
+impl FontFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FontFormat::Type1 => "type1",
+            FontFormat::TrueType => "truetype",
+            FontFormat::Type1C => "type1c",
+            FontFormat::CIDFontType0C => "cidfonttype0c",
+            FontFormat::OpenType => "opentype",
+        }
+    }
+
+    /// Identifies a program by its leading bytes.
+    fn sniff(data: &[u8]) -> Option<Self> {
+        match data {
+            [b'O', b'T', b'T', b'O', ..] => Some(FontFormat::OpenType),
+            [0, 1, 0, 0, ..] | [b't', b'r', b'u', b'e', ..] | [b't', b't', b'c', b'f', ..] => Some(FontFormat::TrueType),
+            [0x80, 0x01, ..] | [b'%', b'!', ..] => Some(FontFormat::Type1),
+            // CFF header: major version 1 and a header size of at least 4
+            [1, _, 4..=255, 1..=4, ..] => Some(FontFormat::Type1C),
+            _ => None,
+        }
+    }
+}
+
+/// A decoded font program from a font descriptor.
+#[derive(Debug, Clone)]
+pub struct FontProgram {
+    pub format: FontFormat,
+    pub data: Vec<u8>,
+    /// For Type 1 programs, the lengths of the cleartext, binary and
+    /// trailer parts of `data`, as needed to write a PFB file.
+    pub segments: Option<[usize; 3]>,
+}
+
+impl FontProgram {
+    /// Loads the program embedded as `/FontFile`, `/FontFile2` or
+    /// `/FontFile3` in a font descriptor, if any.
+    pub fn from_descriptor(doc: &Document, descriptor: &Dictionary) -> Option<Result<Self, PDFError>> {
+        let (key, stream) = [b"FontFile".as_slice(), b"FontFile2", b"FontFile3"].into_iter()
+            .find_map(|key| {
+                let stream = resolve(doc, descriptor.get(key).ok()?).as_stream().ok()?;
+                Some((key, stream))
+            })?;
+        Some(Self::from_stream(doc, key, stream))
+    }
+
+    fn from_stream(doc: &Document, key: &[u8], stream: &Stream) -> Result<Self, PDFError> {
+        let data = decode_stream(stream)?;
+
+        let declared = match key {
+            b"FontFile" => FontFormat::Type1,
+            b"FontFile2" => FontFormat::TrueType,
+            _ => match stream.dict.get(b"Subtype").and_then(Object::as_name_str) {
+                Ok("Type1C") => FontFormat::Type1C,
+                Ok("CIDFontType0C") => FontFormat::CIDFontType0C,
+                Ok("OpenType") => FontFormat::OpenType,
+                subtype => return Err(PDFError::FontError(format!(
+                    "Unsupported FontFile3 subtype {:?}", subtype.ok()
+                ))),
+            },
+        };
+        // Producers mislabel programs often enough that the data decides,
+        // except between the two flavours of CFF
+        let format = match (FontFormat::sniff(&data), declared) {
+            (Some(FontFormat::Type1C), FontFormat::CIDFontType0C) => FontFormat::CIDFontType0C,
+            (Some(FontFormat::TrueType), FontFormat::OpenType) => FontFormat::OpenType,
+            (Some(sniffed), _) => sniffed,
+            (None, declared) => declared,
+        };
+
+        if format != FontFormat::Type1 {
+            return Ok(FontProgram { format, data, segments: None });
+        }
+
+        let length = |key: &[u8]| stream.dict.get(key).ok()
+            .and_then(|o| resolve(doc, o).as_i64().ok())
+            .filter(|&n| n > 0)
+            .map(|n| n as usize);
+        let (data, segments) = if data.starts_with(&[0x80, 0x01]) {
+            type1_from_pfb(&data)
+        } else {
+            type1_from_lengths(&data, length(b"Length1"), length(b"Length2"))
+        };
+        Ok(FontProgram { format, data, segments: Some(segments) })
+    }
+}
+
+/// Splits a Type 1 program into its cleartext, binary and trailer parts.
+/// `Length1` is checked against the `eexec` keyword it should end at, and
+/// a hexadecimal encrypted part is converted to binary.
+fn type1_from_lengths(data: &[u8], length1: Option<usize>, length2: Option<usize>) -> (Vec<u8>, [usize; 3]) {
+    let eexec_end = data.windows(5)
+        .position(|window| window == b"eexec")
+        .map(|start| {
+            let mut end = start + 5;
+            while data.get(end).is_some_and(|b| matches!(b, b'\r' | b'\n' | b' ' | b'\t')) {
+                end += 1;
+            }
+            end
+        });
+    let cleartext_end = eexec_end
+        .or(length1)
+        .unwrap_or(data.len())
+        .min(data.len());
+
+    // Without a usable `Length2`, the trailer is the zeros before a
+    // `cleartomark` following the cleartext, if present
+    let binary_end = match length2.and_then(|length2| cleartext_end.checked_add(length2)) {
+        Some(end) if end <= data.len() => end,
+        _ => {
+            let trailer_start = data[cleartext_end..].windows(11)
+                .rposition(|window| window == b"cleartomark")
+                .map(|mark| {
+                    let mut start = cleartext_end + mark;
+                    let mut zeros = 0;
+                    while start > cleartext_end && zeros < TYPE1_TRAILER_ZEROS {
+                        match data[start - 1] {
+                            b'0' => zeros += 1,
+                            b'\r' | b'\n' | b' ' | b'\t' => {},
+                            _ => break,
+                        }
+                        start -= 1;
+                    }
+                    start
+                });
+            trailer_start.unwrap_or(data.len())
+        },
+    };
+
+    let cleartext = &data[..cleartext_end];
+    let encrypted = &data[cleartext_end..binary_end];
+    let trailer = &data[binary_end..];
+
+    // The encrypted part may be written as hexadecimal digits instead
+    let binary = if encrypted.len() >= 4 && encrypted[..4].iter().all(u8::is_ascii_hexdigit) {
+        ascii_hex_decode(encrypted)
+    } else {
+        encrypted.to_vec()
+    };
+
+    let segments = [cleartext.len(), binary.len(), trailer.len()];
+    let mut program = Vec::with_capacity(segments.iter().sum());
+    program.extend_from_slice(cleartext);
+    program.extend_from_slice(&binary);
+    program.extend_from_slice(trailer);
+    (program, segments)
+}
+
+/// Unwraps a program embedded as a PFB file, whose segments carry a type
+/// and length header.
+fn type1_from_pfb(data: &[u8]) -> (Vec<u8>, [usize; 3]) {
+    let mut parts: [Vec<u8>; 3] = Default::default();
+    let mut position = 0;
+
+    while let [0x80, kind @ (1 | 2), a, b, c, d, ..] = data[position..] {
+        let length = u32::from_le_bytes([a, b, c, d]) as usize;
+        let start = position + 6;
+        let end = (start + length).min(data.len());
+        // ASCII segments after the binary one form the trailer
+        let part = match kind {
+            2 => 1,
+            _ if parts[1].is_empty() => 0,
+            _ => 2,
+        };
+        parts[part].extend_from_slice(&data[start..end]);
+        position = end;
+    }
+
+    let segments = [parts[0].len(), parts[1].len(), parts[2].len()];
+    (parts.concat(), segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_type1_at_eexec_and_trailer() {
+        let mut data = b"%!PS-AdobeFont-1.0\ncurrentfile eexec\n".to_vec();
+        let cleartext = data.len();
+        data.extend_from_slice(&[0x12, 0x34, 0x56]);
+        data.extend_from_slice(&[b'0'; 512]);
+        data.extend_from_slice(b"\ncleartomark\n");
+        let (program, segments) = type1_from_lengths(&data, Some(cleartext), None);
+        assert_eq!(program, data);
+        assert_eq!(segments, [cleartext, 3, data.len() - cleartext - 3]);
+    }
+
+    #[test]
+    fn type1_with_oversized_length1_and_no_length2() {
+        // No `eexec`, and `cleartomark` only in the cleartext
+        let data = b"%!FontType1\n/cleartomark where pop\n".to_vec();
+        let (program, segments) = type1_from_lengths(&data, Some(data.len() * 2), None);
+        assert_eq!(program, data);
+        assert_eq!(segments, [data.len(), 0, 0]);
+    }
+
+    #[test]
+    fn type1_hex_encrypted_part_is_converted_to_binary() {
+        let data = b"currentfile eexec\nA1B2C3\n";
+        let (program, segments) = type1_from_lengths(data, None, None);
+        assert_eq!(segments, [18, 3, 0]);
+        assert_eq!(&program[18..], &[0xA1, 0xB2, 0xC3]);
+    }
+}
@@ -0,0 +1,630 @@
+// src/engines/pdf/src/parser/function.rs
+use lopdf::{Document, Dictionary, Object};
+use super::{PDFError, resolve};
+use super::filters::decode_stream;
+
+/// Nesting limit for stitching functions, guarding against cycles.
+const MAX_FUNCTION_DEPTH: usize = 8;
+
+/// Input limit for sampled functions, each of which doubles the samples
+/// interpolated per evaluation.
+const MAX_SAMPLED_INPUTS: usize = 16;
+
+/// Nesting limit for procedures in PostScript calculator functions.
+const MAX_PROCEDURE_DEPTH: usize = 32;
+
+/// Operand stack limit for PostScript calculator functions.
+const MAX_STACK_DEPTH: usize = 100;
+
+/// A PDF function object (types 0, 2, 3 and 4) mapping `m` inputs to `n`
+/// outputs.
+#[derive(Debug, Clone)]
+pub struct Function {
+    domain: Vec<f32>,
+    range: Option<Vec<f32>>,
+    kind: FunctionKind,
+}
+
+#[derive(Debug, Clone)]
+enum FunctionKind {
+    Sampled {
+        size: Vec<usize>,
+        bits_per_sample: usize,
+        encode: Vec<f32>,
+        decode: Vec<f32>,
+        samples: Vec<u8>,
+    },
+    Exponential {
+        c0: Vec<f32>,
+        c1: Vec<f32>,
+        exponent: f32,
+    },
+    Stitching {
+        functions: Vec<Function>,
+        bounds: Vec<f32>,
+        encode: Vec<f32>,
+    },
+    PostScript(Vec<PostScriptOp>),
+}
+
+/// An element of a type 4 function's program.
+#[derive(Debug, Clone)]
+enum PostScriptOp {
+    Number(f64),
+    Operator(String),
+    If(Vec<PostScriptOp>),
+    IfElse(Vec<PostScriptOp>, Vec<PostScriptOp>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PostScriptValue {
+    Number(f64),
+    Boolean(bool),
+}
+
+impl Function {
+    pub fn from_object(doc: &Document, object: &Object) -> Result<Self, PDFError> {
+        Self::parse(doc, object, 0)
+    }
+
+    fn parse(doc: &Document, object: &Object, depth: usize) -> Result<Self, PDFError> {
+        if depth > MAX_FUNCTION_DEPTH {
+            return Err(PDFError::ParseError("Functions nested too deeply".to_string()));
+        }
+
+        let object = resolve(doc, object);
+        let (dict, stream) = match object {
+            Object::Dictionary(dict) => (dict, None),
+            Object::Stream(stream) => (&stream.dict, Some(stream)),
+            _ => return Err(PDFError::ParseError("Function is not a dictionary or stream".to_string())),
+        };
+        let numbers = |key: &[u8]| numbers(doc, dict, key);
+
+        let domain = numbers(b"Domain").unwrap_or_else(|| vec![0.0, 1.0]);
+        let range = numbers(b"Range");
+        let function_type = dict.get(b"FunctionType").and_then(Object::as_i64)
+            .map_err(|_| PDFError::ParseError("Function without FunctionType".to_string()))?;
+
+        let kind = match (function_type, stream) {
+            (0, Some(stream)) => {
+                let size: Vec<usize> = numbers(b"Size").unwrap_or_default()
+                    .iter()
+                    .map(|&n| n.max(1.0) as usize)
+                    .collect();
+                let Some(range) = &range else {
+                    return Err(PDFError::ParseError("Sampled function without Range".to_string()));
+                };
+                if size.len() > MAX_SAMPLED_INPUTS {
+                    return Err(PDFError::ParseError(format!("Sampled function with {} inputs", size.len())));
+                }
+                let bits_per_sample = dict.get(b"BitsPerSample").and_then(Object::as_i64).unwrap_or(8);
+                if ![1, 2, 4, 8, 12, 16, 24, 32].contains(&bits_per_sample) {
+                    return Err(PDFError::ParseError(format!("Invalid BitsPerSample {}", bits_per_sample)));
+                }
+                // Sample positions are computed in bits
+                let outputs = range.len() / 2;
+                if size.iter().try_fold(outputs * bits_per_sample as usize, |bits, &s| bits.checked_mul(s)).is_none() {
+                    return Err(PDFError::ParseError("Sampled function too large".to_string()));
+                }
+                FunctionKind::Sampled {
+                    encode: numbers(b"Encode").unwrap_or_else(|| {
+                        size.iter().flat_map(|&s| [0.0, (s - 1) as f32]).collect()
+                    }),
+                    decode: numbers(b"Decode").unwrap_or_else(|| range.clone()),
+                    size,
+                    bits_per_sample: bits_per_sample as usize,
+                    samples: decode_stream(stream)?,
+                }
+            },
+            (2, _) => FunctionKind::Exponential {
+                c0: numbers(b"C0").unwrap_or_else(|| vec![0.0]),
+                c1: numbers(b"C1").unwrap_or_else(|| vec![1.0]),
+                exponent: dict.get(b"N").ok().and_then(|o| o.as_float().ok()).unwrap_or(1.0),
+            },
+            (3, _) => {
+                let functions = dict.get(b"Functions").ok()
+                    .and_then(|o| resolve(doc, o).as_array().ok())
+                    .map(|array| array.iter()
+                        .map(|f| Self::parse(doc, f, depth + 1))
+                        .collect::<Result<Vec<_>, _>>())
+                    .transpose()?
+                    .unwrap_or_default();
+                FunctionKind::Stitching {
+                    bounds: numbers(b"Bounds").unwrap_or_default(),
+                    encode: numbers(b"Encode").unwrap_or_else(|| {
+                        functions.iter().flat_map(|_| [0.0, 1.0]).collect()
+                    }),
+                    functions,
+                }
+            },
+            (4, Some(stream)) => FunctionKind::PostScript(parse_postscript(&decode_stream(stream)?)?),
+            (other, _) => return Err(PDFError::ParseError(format!("Unsupported function type {}", other))),
+        };
+
+        Ok(Function { domain, range, kind })
+    }
+
+    /// Evaluates the function, clipping inputs to the domain and outputs
+    /// to the range.
+    pub fn evaluate(&self, inputs: &[f32]) -> Vec<f32> {
+        let inputs: Vec<f32> = inputs.iter()
+            .enumerate()
+            .map(|(i, &x)| clip(x, self.domain.get(i * 2).copied(), self.domain.get(i * 2 + 1).copied()))
+            .collect();
+
+        let mut outputs = match &self.kind {
+            FunctionKind::Sampled { size, bits_per_sample, encode, decode, samples } => {
+                self.evaluate_sampled(&inputs, size, *bits_per_sample, encode, decode, samples)
+            },
+            FunctionKind::Exponential { c0, c1, exponent } => {
+                let x = inputs.first().copied().unwrap_or(0.0);
+                let power = x.powf(*exponent);
+                c0.iter().zip(c1).map(|(a, b)| a + power * (b - a)).collect()
+            },
+            FunctionKind::Stitching { functions, bounds, encode } => {
+                let x = inputs.first().copied().unwrap_or(0.0);
+                let index = bounds.iter().take_while(|&&bound| x >= bound).count()
+                    .min(functions.len().saturating_sub(1));
+                let low = match index {
+                    0 => self.domain.first().copied().unwrap_or(0.0),
+                    _ => bounds[index - 1],
+                };
+                let high = bounds.get(index).copied()
+                    .unwrap_or_else(|| self.domain.get(1).copied().unwrap_or(1.0));
+                let e0 = encode.get(index * 2).copied().unwrap_or(0.0);
+                let e1 = encode.get(index * 2 + 1).copied().unwrap_or(1.0);
+                match functions.get(index) {
+                    Some(function) => function.evaluate(&[interpolate(x, low, high, e0, e1)]),
+                    None => Vec::new(),
+                }
+            },
+            FunctionKind::PostScript(program) => run_postscript(program, &inputs),
+        };
+
+        if let Some(range) = &self.range {
+            for (i, y) in outputs.iter_mut().enumerate() {
+                *y = clip(*y, range.get(i * 2).copied(), range.get(i * 2 + 1).copied());
+            }
+        }
+        outputs
+    }
+
+    /// Multilinear interpolation between the samples surrounding the
+    /// encoded input. Cubic spline `/Order 3` is approximated linearly.
+    fn evaluate_sampled(
+        &self,
+        inputs: &[f32],
+        size: &[usize],
+        bits_per_sample: usize,
+        encode: &[f32],
+        decode: &[f32],
+        samples: &[u8],
+    ) -> Vec<f32> {
+        let outputs = self.range.as_ref().map_or(0, |r| r.len() / 2);
+        let dimensions = size.len().min(inputs.len());
+        let max_sample = ((1u64 << bits_per_sample) - 1) as f32;
+
+        // Position of each input between two neighbouring sample indices
+        let mut floors = Vec::with_capacity(dimensions);
+        let mut fractions = Vec::with_capacity(dimensions);
+        for i in 0..dimensions {
+            let domain_low = self.domain.get(i * 2).copied().unwrap_or(0.0);
+            let domain_high = self.domain.get(i * 2 + 1).copied().unwrap_or(1.0);
+            let e0 = encode.get(i * 2).copied().unwrap_or(0.0);
+            let e1 = encode.get(i * 2 + 1).copied().unwrap_or((size[i] - 1) as f32);
+            let e = interpolate(inputs[i], domain_low, domain_high, e0, e1)
+                .max(0.0)
+                .min((size[i] - 1) as f32);
+            let floor = (e.floor() as usize).min(size[i].saturating_sub(2));
+            floors.push(floor);
+            fractions.push(e - floor as f32);
+        }
+
+        let sample = |index: usize, output: usize| -> f32 {
+            let bit = (index * outputs + output) * bits_per_sample;
+            let mut value: u64 = 0;
+            for b in 0..bits_per_sample {
+                let position = bit + b;
+                let byte = samples.get(position / 8).copied().unwrap_or(0);
+                value = value << 1 | u64::from(byte >> (7 - position % 8) & 1);
+            }
+            value as f32
+        };
+
+        (0..outputs).map(|output| {
+            let mut total = 0.0;
+            for corner in 0..1usize << dimensions {
+                let mut weight = 1.0;
+                let mut index = 0;
+                let mut stride = 1;
+                for i in 0..dimensions {
+                    let upper = corner >> i & 1 == 1 && size[i] > 1;
+                    weight *= if upper { fractions[i] } else { 1.0 - fractions[i] };
+                    index += (floors[i] + usize::from(upper)) * stride;
+                    stride *= size[i];
+                }
+                if weight > 0.0 {
+                    total += weight * sample(index, output);
+                }
+            }
+            let d0 = decode.get(output * 2).copied().unwrap_or(0.0);
+            let d1 = decode.get(output * 2 + 1).copied().unwrap_or(1.0);
+            interpolate(total, 0.0, max_sample, d0, d1)
+        }).collect()
+    }
+}
+
+fn numbers(doc: &Document, dict: &Dictionary, key: &[u8]) -> Option<Vec<f32>> {
+    dict.get(key).ok()
+        .and_then(|o| resolve(doc, o).as_array().ok())
+        .map(|a| a.iter().filter_map(|n| n.as_float().ok()).collect())
+}
+
+fn interpolate(x: f32, x0: f32, x1: f32, y0: f32, y1: f32) -> f32 {
+    if x1 == x0 {
+        y0
+    } else {
+        y0 + (x - x0) * (y1 - y0) / (x1 - x0)
+    }
+}
+
+fn clip(x: f32, low: Option<f32>, high: Option<f32>) -> f32 {
+    let x = low.map_or(x, |low| x.max(low));
+    high.map_or(x, |high| x.min(high))
+}
+
+/// Parses the `{ ... }` program of a PostScript calculator function.
+fn parse_postscript(data: &[u8]) -> Result<Vec<PostScriptOp>, PDFError> {
+    let mut position = 0;
+    // Skip to the outermost opening brace
+    while position < data.len() && data[position] != b'{' {
+        position += 1;
+    }
+    position += 1;
+    parse_postscript_block(data, &mut position, 0)
+}
+
+fn parse_postscript_block(data: &[u8], position: &mut usize, depth: usize) -> Result<Vec<PostScriptOp>, PDFError> {
+    if depth > MAX_PROCEDURE_DEPTH {
+        return Err(PDFError::ParseError("PostScript procedures nested too deeply".to_string()));
+    }
+    let mut ops = Vec::new();
+    // Procedures waiting for their `if` or `ifelse`
+    let mut blocks: Vec<Vec<PostScriptOp>> = Vec::new();
+
+    while *position < data.len() {
+        let b = data[*position];
+        match b {
+            b'{' => {
+                *position += 1;
+                blocks.push(parse_postscript_block(data, position, depth + 1)?);
+            },
+            b'}' => {
+                *position += 1;
+                break;
+            },
+            _ if b.is_ascii_whitespace() => *position += 1,
+            _ => {
+                let start = *position;
+                while *position < data.len()
+                    && !data[*position].is_ascii_whitespace()
+                    && !matches!(data[*position], b'{' | b'}')
+                {
+                    *position += 1;
+                }
+                let word = String::from_utf8_lossy(&data[start..*position]).into_owned();
+                match word.as_str() {
+                    "if" => {
+                        if let Some(block) = blocks.pop() {
+                            ops.push(PostScriptOp::If(block));
+                        }
+                    },
+                    "ifelse" => {
+                        if let (Some(otherwise), Some(then)) = (blocks.pop(), blocks.pop()) {
+                            ops.push(PostScriptOp::IfElse(then, otherwise));
+                        }
+                    },
+                    _ => match word.parse::<f64>() {
+                        Ok(n) => ops.push(PostScriptOp::Number(n)),
+                        Err(_) => ops.push(PostScriptOp::Operator(word)),
+                    },
+                }
+            },
+        }
+    }
+    Ok(ops)
+}
+
+fn run_postscript(program: &[PostScriptOp], inputs: &[f32]) -> Vec<f32> {
+    let mut stack: Vec<PostScriptValue> = inputs.iter()
+        .map(|&x| PostScriptValue::Number(f64::from(x)))
+        .collect();
+    execute_postscript(program, &mut stack);
+    stack.iter()
+        .map(|value| match value {
+            PostScriptValue::Number(n) => *n as f32,
+            PostScriptValue::Boolean(b) => f32::from(u8::from(*b)),
+        })
+        .collect()
+}
+
+/// Executes a program; malformed programs stop at the failing operator
+/// and leave the stack as it was then.
+fn execute_postscript(program: &[PostScriptOp], stack: &mut Vec<PostScriptValue>) -> Option<()> {
+    use PostScriptValue::{Boolean, Number};
+
+    for op in program {
+        if stack.len() > MAX_STACK_DEPTH {
+            return None;
+        }
+        match op {
+            PostScriptOp::Number(n) => stack.push(Number(*n)),
+            PostScriptOp::If(then) => {
+                if let Boolean(true) = stack.pop()? {
+                    execute_postscript(then, stack)?;
+                }
+            },
+            PostScriptOp::IfElse(then, otherwise) => {
+                match stack.pop()? {
+                    Boolean(true) => execute_postscript(then, stack)?,
+                    _ => execute_postscript(otherwise, stack)?,
+                }
+            },
+            PostScriptOp::Operator(name) => {
+                let number = |value: PostScriptValue| match value {
+                    Number(n) => Some(n),
+                    Boolean(_) => None,
+                };
+                // Counts and stack positions must not be negative
+                let count = |value: PostScriptValue| number(value)
+                    .filter(|n| *n >= 0.0)
+                    .map(|n| n as usize);
+                match name.as_str() {
+                    "true" => stack.push(Boolean(true)),
+                    "false" => stack.push(Boolean(false)),
+                    // Unary numeric operators
+                    "abs" | "ceiling" | "cos" | "cvi" | "cvr" | "floor" | "ln" | "log"
+                    | "neg" | "round" | "sin" | "sqrt" | "truncate" => {
+                        let x = number(stack.pop()?)?;
+                        stack.push(Number(match name.as_str() {
+                            "abs" => x.abs(),
+                            "ceiling" => x.ceil(),
+                            // Angles are in degrees
+                            "cos" => x.to_radians().cos(),
+                            "sin" => x.to_radians().sin(),
+                            "cvi" | "truncate" => x.trunc(),
+                            "floor" => x.floor(),
+                            "ln" => x.ln(),
+                            "log" => x.log10(),
+                            "neg" => -x,
+                            "round" => (x + 0.5).floor(),
+                            "sqrt" => x.sqrt(),
+                            _ => x,
+                        }));
+                    },
+                    // Binary numeric operators
+                    "add" | "sub" | "mul" | "div" | "idiv" | "mod" | "exp" | "atan" => {
+                        let b = number(stack.pop()?)?;
+                        let a = number(stack.pop()?)?;
+                        stack.push(Number(match name.as_str() {
+                            "add" => a + b,
+                            "sub" => a - b,
+                            "mul" => a * b,
+                            "div" => a / b,
+                            "idiv" if b != 0.0 => (a.trunc() / b.trunc()).trunc(),
+                            "mod" if b != 0.0 => a.trunc() % b.trunc(),
+                            "exp" => a.powf(b),
+                            "atan" => {
+                                let angle = a.atan2(b).to_degrees();
+                                if angle < 0.0 { angle + 360.0 } else { angle }
+                            },
+                            _ => 0.0,
+                        }));
+                    },
+                    "eq" | "ne" | "gt" | "ge" | "lt" | "le" => {
+                        let b = stack.pop()?;
+                        let a = stack.pop()?;
+                        let result = match (a, b) {
+                            (Number(a), Number(b)) => match name.as_str() {
+                                "eq" => a == b,
+                                "ne" => a != b,
+                                "gt" => a > b,
+                                "ge" => a >= b,
+                                "lt" => a < b,
+                                _ => a <= b,
+                            },
+                            (Boolean(a), Boolean(b)) => match name.as_str() {
+                                "eq" => a == b,
+                                "ne" => a != b,
+                                _ => return None,
+                            },
+                            _ => return None,
+                        };
+                        stack.push(Boolean(result));
+                    },
+                    "and" | "or" | "xor" => {
+                        let b = stack.pop()?;
+                        let a = stack.pop()?;
+                        stack.push(match (a, b) {
+                            (Boolean(a), Boolean(b)) => Boolean(match name.as_str() {
+                                "and" => a && b,
+                                "or" => a || b,
+                                _ => a ^ b,
+                            }),
+                            (Number(a), Number(b)) => {
+                                let (a, b) = (a as i64, b as i64);
+                                Number(match name.as_str() {
+                                    "and" => a & b,
+                                    "or" => a | b,
+                                    _ => a ^ b,
+                                } as f64)
+                            },
+                            _ => return None,
+                        });
+                    },
+                    "not" => {
+                        let value = stack.pop()?;
+                        stack.push(match value {
+                            Boolean(b) => Boolean(!b),
+                            Number(n) => Number(!(n as i64) as f64),
+                        });
+                    },
+                    "bitshift" => {
+                        let shift = number(stack.pop()?)? as i64;
+                        let value = number(stack.pop()?)? as i64;
+                        let shifted = if shift >= 0 {
+                            value.checked_shl(shift as u32).unwrap_or(0)
+                        } else {
+                            value.checked_shr(shift.unsigned_abs() as u32).unwrap_or(0)
+                        };
+                        stack.push(Number(shifted as f64));
+                    },
+                    "pop" => {
+                        stack.pop()?;
+                    },
+                    "exch" => {
+                        let b = stack.pop()?;
+                        let a = stack.pop()?;
+                        stack.push(b);
+                        stack.push(a);
+                    },
+                    "dup" => {
+                        let top = *stack.last()?;
+                        stack.push(top);
+                    },
+                    "copy" => {
+                        let count = count(stack.pop()?)?;
+                        let start = stack.len().checked_sub(count)?;
+                        stack.extend_from_within(start..);
+                    },
+                    "index" => {
+                        let offset = count(stack.pop()?)?;
+                        let value = *stack.get(stack.len().checked_sub(offset.checked_add(1)?)?)?;
+                        stack.push(value);
+                    },
+                    "roll" => {
+                        let shift = number(stack.pop()?)? as i64;
+                        let count = count(stack.pop()?)?;
+                        let start = stack.len().checked_sub(count)?;
+                        if count > 0 {
+                            let shift = shift.rem_euclid(count as i64) as usize;
+                            stack[start..].rotate_right(shift);
+                        }
+                    },
+                    _ => return None,
+                }
+            },
+        }
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    fn numbers(values: &[f32]) -> Vec<Object> {
+        values.iter().map(|&n| Object::Real(n)).collect()
+    }
+
+    fn postscript(program: &str) -> Result<Function, PDFError> {
+        let stream = Stream::new(dictionary! {
+            "FunctionType" => 4,
+            "Domain" => numbers(&[-1000.0, 1000.0, -1000.0, 1000.0]),
+            "Range" => numbers(&[-1000.0, 1000.0, -1000.0, 1000.0, -1000.0, 1000.0]),
+        }, program.as_bytes().to_vec());
+        Function::from_object(&Document::new(), &Object::Stream(stream))
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{:?} vs {:?}", actual, expected);
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-3, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn exponential_interpolates_and_clips() {
+        let function = Function::from_object(&Document::new(), &Object::Dictionary(dictionary! {
+            "FunctionType" => 2,
+            "Domain" => numbers(&[0.0, 1.0]),
+            "C0" => numbers(&[0.0, 1.0]),
+            "C1" => numbers(&[1.0, 0.0]),
+            "N" => 2,
+        })).unwrap();
+        assert_close(&function.evaluate(&[0.5]), &[0.25, 0.75]);
+        assert_close(&function.evaluate(&[2.0]), &[1.0, 0.0]);
+    }
+
+    #[test]
+    fn stitching_selects_by_bounds() {
+        let linear = |c0: f32, c1: f32| Object::Dictionary(dictionary! {
+            "FunctionType" => 2, "Domain" => numbers(&[0.0, 1.0]),
+            "C0" => numbers(&[c0]), "C1" => numbers(&[c1]), "N" => 1,
+        });
+        let function = Function::from_object(&Document::new(), &Object::Dictionary(dictionary! {
+            "FunctionType" => 3,
+            "Domain" => numbers(&[0.0, 1.0]),
+            "Functions" => vec![linear(0.0, 1.0), linear(10.0, 20.0)],
+            "Bounds" => numbers(&[0.5]),
+            "Encode" => numbers(&[0.0, 1.0, 0.0, 1.0]),
+        })).unwrap();
+        assert_close(&function.evaluate(&[0.25]), &[0.5]);
+        assert_close(&function.evaluate(&[0.75]), &[15.0]);
+    }
+
+    #[test]
+    fn sampled_interpolates_between_samples() {
+        let stream = Stream::new(dictionary! {
+            "FunctionType" => 0,
+            "Domain" => numbers(&[0.0, 1.0]),
+            "Range" => numbers(&[0.0, 1.0]),
+            "Size" => vec![Object::Integer(3)],
+            "BitsPerSample" => 8,
+        }, vec![0, 255, 0]);
+        let function = Function::from_object(&Document::new(), &Object::Stream(stream)).unwrap();
+        assert_close(&function.evaluate(&[0.25]), &[0.5]);
+        assert_close(&function.evaluate(&[0.5]), &[1.0]);
+    }
+
+    #[test]
+    fn sampled_rejects_invalid_bits_and_inputs() {
+        let sampled = |bits: i64, size: Vec<Object>| Function::from_object(&Document::new(), &Object::Stream(Stream::new(dictionary! {
+            "FunctionType" => 0,
+            "Domain" => numbers(&[0.0, 1.0]),
+            "Range" => numbers(&[0.0, 1.0]),
+            "Size" => size,
+            "BitsPerSample" => bits,
+        }, vec![0; 4])));
+        assert!(sampled(7, vec![Object::Integer(2)]).is_err());
+        assert!(sampled(64, vec![Object::Integer(2)]).is_err());
+        assert!(sampled(8, vec![Object::Integer(2); 64]).is_err());
+        assert!(sampled(8, vec![Object::Integer(i64::MAX); 2]).is_err());
+        assert!(sampled(12, vec![Object::Integer(2)]).is_ok());
+    }
+
+    #[test]
+    fn postscript_arithmetic_and_conditionals() {
+        let function = postscript("{ 2 copy add 3 1 roll exch sub dup 0 lt { neg } if }").unwrap();
+        assert_close(&function.evaluate(&[3.0, 5.0]), &[8.0, 2.0]);
+        let function = postscript("{ 1 index 0 gt { pop 1 } { pop 0 } ifelse }").unwrap();
+        assert_close(&function.evaluate(&[-1.0, 4.0]), &[-1.0, 0.0]);
+    }
+
+    #[test]
+    fn postscript_rejects_bad_stack_operands() {
+        // The program stops at `index`, leaving the stack as it was
+        for program in ["{ 1e30 index }", "{ -1 index }", "{ -2 copy }", "{ -1 1 roll }"] {
+            let function = postscript(program).unwrap();
+            assert_eq!(function.evaluate(&[1.0, 2.0]).len(), 2, "{}", program);
+        }
+    }
+
+    #[test]
+    fn postscript_nesting_is_limited() {
+        let program = format!("{{ {}{} }}", "{ ".repeat(10_000), "} ".repeat(10_000));
+        assert!(postscript(&program).is_err());
+        assert!(postscript("{ { { 1 } if } if }").is_ok());
+    }
+}
@@ -0,0 +1,439 @@
+// src/engines/pdf/src/parser/image.rs
+use std::collections::HashMap;
+use lopdf::{Document, Dictionary, Object, Stream};
+use super::{PDFError, resolve};
+use super::color::ColorSpace;
+use super::filters::decode_image_stream;
+use super::lexer::Operand;
+use crate::content::{ImageData, ImageMask};
+
+/// Image dictionary entries describing how to interpret sample data.
+#[derive(Debug, Clone)]
+pub struct ImageParams {
+    pub width: u32,
+    pub height: u32,
+    pub bits_per_component: u8,
+    /// `None` for stencil masks (`/ImageMask true`).
+    pub color_space: Option<ColorSpace>,
+    /// `/Decode` ranges, one pair per component.
+    pub decode: Option<Vec<f32>>,
+    /// Sample ranges from a `/Mask` array; matching pixels are transparent.
+    pub color_key: Option<Vec<u32>>,
+    pub interpolate: bool,
+}
+
+/// An image with its samples converted for the renderer.
+pub struct DecodedImage {
+    pub params: ImageParams,
+    pub data: ImageData,
+    pub mask: Option<ImageMask>,
+    /// Why the image's mask could not be used; the image is drawn unmasked.
+    pub mask_error: Option<PDFError>,
+}
+
+impl ImageParams {
+    pub fn from_dictionary(doc: &Document, dict: &Dictionary) -> Result<Self, PDFError> {
+        let integer = |key: &[u8]| dict.get(key).ok()
+            .and_then(|o| resolve(doc, o).as_i64().ok())
+            .filter(|n| *n > 0);
+        let numbers = |key: &[u8]| dict.get(key).ok()
+            .and_then(|o| resolve(doc, o).as_array().ok())
+            .map(|a| a.iter().filter_map(|n| n.as_float().ok()).collect::<Vec<f32>>());
+
+        let (Some(width), Some(height)) = (integer(b"Width"), integer(b"Height")) else {
+            return Err(PDFError::ParseError("Image without dimensions".to_string()));
+        };
+
+        let image_mask = dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false);
+        let color_space = if image_mask {
+            None
+        } else {
+            let space = dict.get(b"ColorSpace").ok()
+                .and_then(|o| ColorSpace::from_object(doc, o))
+                .unwrap_or(ColorSpace::DeviceGray);
+            Some(space)
+        };
+
+        Ok(ImageParams {
+            width: width as u32,
+            height: height as u32,
+            bits_per_component: if image_mask {
+                1
+            } else {
+                integer(b"BitsPerComponent").unwrap_or(8) as u8
+            },
+            color_space,
+            decode: numbers(b"Decode"),
+            color_key: dict.get(b"Mask").ok()
+                .and_then(|o| resolve(doc, o).as_array().ok())
+                .map(|a| a.iter().filter_map(|n| n.as_i64().ok()).map(|n| n as u32).collect()),
+            interpolate: dict.get(b"Interpolate").and_then(Object::as_bool).unwrap_or(false),
+        })
+    }
+
+    /// Length in bytes of the unfiltered sample data, or `None` if the
+    /// declared dimensions overflow.
+    pub fn data_length(&self) -> Option<usize> {
+        self.row_length()?.checked_mul(self.height as usize)
+    }
+
+    fn components(&self) -> usize {
+        self.color_space.as_ref().map_or(1, ColorSpace::components)
+    }
+
+    fn row_length(&self) -> Option<usize> {
+        let bits = (self.width as usize)
+            .checked_mul(self.components())?
+            .checked_mul(self.bits_per_component as usize)?;
+        Some(bits.div_ceil(8))
+    }
+
+    /// Decode range of component `index`, defaulting to the full range of
+    /// the color space.
+    fn decode_range(&self, index: usize) -> (f32, f32) {
+        if let Some(decode) = &self.decode {
+            if let (Some(&min), Some(&max)) = (decode.get(index * 2), decode.get(index * 2 + 1)) {
+                return (min, max);
+            }
+        }
+        match &self.color_space {
+            Some(ColorSpace::Indexed { .. }) => (0.0, self.max_sample() as f32),
+            Some(ColorSpace::Lab { range, .. }) => match index {
+                0 => (0.0, 100.0),
+                1 => (range[0], range[1]),
+                _ => (range[2], range[3]),
+            },
+            _ => (0.0, 1.0),
+        }
+    }
+
+    fn max_sample(&self) -> u32 {
+        (1u32 << self.bits_per_component.min(16)) - 1
+    }
+
+    /// Converts raw samples to 8-bit RGBA. Stencil masks are painted in
+    /// `fill_color` where their samples select it. The declared dimensions
+    /// must be covered by `data`, so that they cannot request more memory
+    /// than the stream justifies.
+    pub fn to_rgba(&self, data: &[u8], fill_color: [f32; 4]) -> Result<Vec<u8>, PDFError> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let bpc = self.bits_per_component as usize;
+        let count = self.components();
+        let (Some(row_length), Some(length), Some(size)) = (
+            self.row_length(),
+            self.data_length(),
+            width.checked_mul(height).and_then(|pixels| pixels.checked_mul(4)),
+        ) else {
+            return Err(PDFError::ParseError(format!("Image dimensions {}x{} are too large", width, height)));
+        };
+        if data.len() < length {
+            return Err(PDFError::ParseError(format!(
+                "Image data of {} bytes is shorter than the {} bytes of a {}x{} image", data.len(), length, width, height
+            )));
+        }
+        let mut output = Vec::with_capacity(size);
+
+        let Some(space) = &self.color_space else {
+            // A sample of 0 paints unless the decode array is inverted
+            let inverted = self.decode.as_ref().and_then(|d| d.first()).is_some_and(|&d| d >= 1.0);
+            let [r, g, b, a] = fill_color.map(to_byte);
+            for y in 0..height {
+                for x in 0..width {
+                    let painted = (read_sample(data, y * row_length * 8 + x, 1) == 0) != inverted;
+                    output.extend_from_slice(&[r, g, b, if painted { a } else { 0 }]);
+                }
+            }
+            return Ok(output);
+        };
+
+        let max = self.max_sample() as f32;
+        let ranges: Vec<(f32, f32)> = (0..count).map(|index| self.decode_range(index)).collect();
+        let mut components = vec![0.0; count];
+        let mut samples = vec![0u32; count];
+
+        for y in 0..height {
+            for x in 0..width {
+                let offset = y * row_length * 8 + x * count * bpc;
+                for (index, sample) in samples.iter_mut().enumerate() {
+                    *sample = read_sample(data, offset + index * bpc, bpc);
+                    let (min, range_max) = ranges[index];
+                    components[index] = min + *sample as f32 * (range_max - min) / max;
+                }
+
+                let [r, g, b] = space.color_from_components(&components).to_rgb().map(to_byte);
+                output.extend_from_slice(&[r, g, b, if self.is_keyed(&samples) { 0 } else { 255 }]);
+            }
+        }
+        Ok(output)
+    }
+
+    fn is_keyed(&self, samples: &[u32]) -> bool {
+        let Some(key) = &self.color_key else {
+            return false;
+        };
+        key.len() >= samples.len() * 2 && samples.iter()
+            .enumerate()
+            .all(|(index, &sample)| key[index * 2] <= sample && sample <= key[index * 2 + 1])
+    }
+}
+
+impl DecodedImage {
+    /// Decodes an image XObject and its mask.
+    pub fn from_stream(doc: &Document, stream: &Stream, fill_color: [f32; 4]) -> Result<Self, PDFError> {
+        let params = ImageParams::from_dictionary(doc, &stream.dict)?;
+        Self::from_params(doc, stream, params, fill_color)
+    }
+
+    /// Decodes an image stream whose parameters were already read, e.g.
+    /// with a color space resolved from page resources.
+    pub fn from_params(
+        doc: &Document,
+        stream: &Stream,
+        params: ImageParams,
+        fill_color: [f32; 4],
+    ) -> Result<Self, PDFError> {
+        let (data, filter) = decode_image_stream(stream)?;
+        let data = Self::image_data(&params, data, filter.as_deref(), fill_color)?;
+
+        // `/SMask` takes precedence over a stencil `/Mask`
+        let mask_stream = |key: &[u8]| stream.dict.get(key).ok()
+            .and_then(|o| resolve(doc, o).as_stream().ok());
+        let mask = match (mask_stream(b"SMask"), mask_stream(b"Mask")) {
+            (Some(soft_mask), _) => Some(Self::decode_mask(doc, soft_mask, false)),
+            (None, Some(stencil)) => Some(Self::decode_mask(doc, stencil, true)),
+            _ => None,
+        };
+        let (mask, mask_error) = match mask.transpose() {
+            Ok(mask) => (mask, None),
+            Err(e) => (None, Some(e)),
+        };
+
+        Ok(DecodedImage { params, data, mask, mask_error })
+    }
+
+    /// Converts decoded stream data to `ImageData`. Data still compressed by
+    /// `filter` is passed on when the browser can decode it.
+    pub fn image_data(
+        params: &ImageParams,
+        data: Vec<u8>,
+        filter: Option<&str>,
+        fill_color: [f32; 4],
+    ) -> Result<ImageData, PDFError> {
+        match filter {
+            None => Ok(ImageData::Rgba(params.to_rgba(&data, fill_color)?)),
+            Some("DCTDecode") => Ok(ImageData::Jpeg(data)),
+            Some("JPXDecode") => Ok(ImageData::Jpeg2000(data)),
+            Some(other) => Err(PDFError::DecompressionError(format!("Unsupported image filter {}", other))),
+        }
+    }
+
+    fn decode_mask(doc: &Document, stream: &Stream, stencil: bool) -> Result<ImageMask, PDFError> {
+        let mut params = ImageParams::from_dictionary(doc, &stream.dict)?;
+        if stencil {
+            params.color_space = None;
+            params.bits_per_component = 1;
+        } else {
+            params.color_space = Some(ColorSpace::DeviceGray);
+        }
+        // The mask is applied to samples, so it cannot be left for the
+        // browser to decode
+        let (data, filter) = decode_image_stream(stream)?;
+        if let Some(filter) = filter {
+            return Err(PDFError::DecompressionError(format!("Unsupported image mask filter {}", filter)));
+        }
+
+        // Stencil masks are opaque where painted; soft masks use their gray level
+        let rgba = params.to_rgba(&data, [1.0, 1.0, 1.0, 1.0])?;
+        let channel = if stencil { 3 } else { 0 };
+        Ok(ImageMask {
+            width: params.width,
+            height: params.height,
+            alpha: rgba.chunks(4).map(|pixel| pixel[channel]).collect(),
+        })
+    }
+}
+
+/// Builds an image stream from the dictionary and data of an inline image
+/// (`BI ... ID ... EI`), expanding abbreviated keys and filter names.
+pub fn inline_image_stream(entries: &HashMap<String, Operand>, data: Vec<u8>) -> Stream {
+    let mut dict = Dictionary::new();
+    for (key, value) in entries {
+        let key = expand_inline_key(key);
+        let mut value = value.to_object();
+        if key == "Filter" {
+            let names = match &mut value {
+                Object::Array(names) => names.iter_mut().collect(),
+                name => vec![name],
+            };
+            for name in names {
+                if let Object::Name(bytes) = name {
+                    *bytes = expand_inline_filter(&String::from_utf8_lossy(bytes)).as_bytes().to_vec();
+                }
+            }
+        }
+        dict.set(key, value);
+    }
+    Stream::new(dict, data)
+}
+
+fn expand_inline_key(key: &str) -> &str {
+    match key {
+        "W" => "Width",
+        "H" => "Height",
+        "BPC" => "BitsPerComponent",
+        "CS" => "ColorSpace",
+        "D" => "Decode",
+        "DP" => "DecodeParms",
+        "F" => "Filter",
+        "IM" => "ImageMask",
+        "I" => "Interpolate",
+        "L" => "Length",
+        other => other,
+    }
+}
+
+fn expand_inline_filter(name: &str) -> &str {
+    match name {
+        "AHx" => "ASCIIHexDecode",
+        "A85" => "ASCII85Decode",
+        "LZW" => "LZWDecode",
+        "Fl" => "FlateDecode",
+        "RL" => "RunLengthDecode",
+        "CCF" => "CCITTFaxDecode",
+        "DCT" => "DCTDecode",
+        other => other,
+    }
+}
+
+/// Reads a `bits`-wide sample at bit offset `position`, treating data
+/// past the end of a truncated stream as zero. 16-bit samples are kept at
+/// full precision.
+fn read_sample(data: &[u8], position: usize, bits: usize) -> u32 {
+    let byte = |index: usize| u32::from(data.get(index).copied().unwrap_or(0));
+    match bits {
+        8 => byte(position / 8),
+        16 => byte(position / 8) << 8 | byte(position / 8 + 1),
+        1 | 2 | 4 => {
+            let shift = 8 - bits - position % 8;
+            byte(position / 8) >> shift & ((1 << bits) - 1)
+        },
+        _ => 0,
+    }
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::filters::{is_supported_filter, stream_filters};
+    use crate::parser::lexer::{Lexer, Token};
+    use lopdf::dictionary;
+
+    /// Reads an inline image the way the content parser does, leaving the
+    /// lexer after `EI`.
+    fn read_inline_image(lexer: &mut Lexer) -> Stream {
+        let mut entries = HashMap::new();
+        let mut key: Option<String> = None;
+        loop {
+            match lexer.next_token().unwrap() {
+                Token::Keyword(keyword) if keyword == "ID" => break,
+                Token::Keyword(_) => {},
+                Token::Operand(operand) => match key.take() {
+                    Some(key) => {
+                        entries.insert(key, operand);
+                    },
+                    None => key = operand.as_name().map(str::to_string),
+                },
+            }
+        }
+        let data = lexer.read_inline_image_data(None);
+        inline_image_stream(&entries, data)
+    }
+
+    #[test]
+    fn inline_fax_image_is_unsupported() {
+        let content = b"BI /W 8 /H 2 /IM true /F /CCF /DP << /K -1 /Columns 8 >> ID \x26\xA0\x00 EI Q";
+        let mut lexer = Lexer::new(content);
+        assert!(matches!(lexer.next_token(), Some(Token::Keyword(keyword)) if keyword == "BI"));
+        let stream = read_inline_image(&mut lexer);
+
+        let filters = stream_filters(&stream);
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].name, "CCITTFaxDecode");
+        assert!(!is_supported_filter(&filters[0].name));
+        assert_eq!(stream.content, b"\x26\xA0\x00");
+        assert!(DecodedImage::from_stream(&Document::new(), &stream, [0.0, 0.0, 0.0, 1.0]).is_err());
+        assert!(matches!(lexer.next_token(), Some(Token::Keyword(keyword)) if keyword == "Q"));
+    }
+
+    #[test]
+    fn inline_image_keys_and_filters_are_expanded() {
+        let mut lexer = Lexer::new(b"BI /W 2 /H 1 /BPC 8 /CS /G /F [/AHx] ID 00FF> EI");
+        lexer.next_token();
+        let stream = read_inline_image(&mut lexer);
+        assert_eq!(stream.dict.get(b"Width").and_then(Object::as_i64).unwrap(), 2);
+        assert_eq!(stream.dict.get(b"ColorSpace").and_then(Object::as_name_str).unwrap(), "G");
+
+        let image = DecodedImage::from_stream(&Document::new(), &stream, [0.0; 4]).unwrap();
+        match image.data {
+            ImageData::Rgba(rgba) => assert_eq!(rgba, [0, 0, 0, 255, 255, 255, 255, 255]),
+            _ => panic!("expected RGBA data"),
+        }
+    }
+
+    #[test]
+    fn short_image_data_is_rejected() {
+        let stream = Stream::new(dictionary! {
+            "Width" => 1_000_000, "Height" => 1_000_000, "ColorSpace" => "DeviceRGB", "BitsPerComponent" => 8,
+        }, vec![0; 16]);
+        assert!(DecodedImage::from_stream(&Document::new(), &stream, [0.0; 4]).is_err());
+    }
+
+    #[test]
+    fn reads_packed_samples() {
+        assert_eq!(read_sample(&[0b1011_0001], 0, 1), 1);
+        assert_eq!(read_sample(&[0b1011_0001], 1, 1), 0);
+        assert_eq!(read_sample(&[0b1011_0001], 4, 4), 1);
+        assert_eq!(read_sample(&[0x12, 0x34], 0, 16), 0x1234);
+        assert_eq!(read_sample(&[0x12], 8, 8), 0);
+    }
+
+    #[test]
+    fn soft_mask_and_color_key() {
+        let mut doc = Document::new();
+        let soft_mask = doc.add_object(Stream::new(dictionary! {
+            "Width" => 2, "Height" => 1, "ColorSpace" => "DeviceGray", "BitsPerComponent" => 8,
+        }, vec![0, 255]));
+        let stream = Stream::new(dictionary! {
+            "Width" => 2, "Height" => 1, "ColorSpace" => "DeviceGray", "BitsPerComponent" => 8,
+            "SMask" => Object::Reference(soft_mask),
+        }, vec![0, 128]);
+        let image = DecodedImage::from_stream(&doc, &stream, [0.0; 4]).unwrap();
+        assert_eq!(image.mask.unwrap().alpha, [0, 255]);
+        assert!(image.mask_error.is_none());
+
+        let keyed = Stream::new(dictionary! {
+            "Width" => 2, "Height" => 1, "ColorSpace" => "DeviceGray", "BitsPerComponent" => 8,
+            "Mask" => vec![Object::Integer(100), Object::Integer(200)],
+        }, vec![50, 150]);
+        match DecodedImage::from_stream(&doc, &keyed, [0.0; 4]).unwrap().data {
+            ImageData::Rgba(rgba) => assert_eq!(rgba, [50, 50, 50, 255, 150, 150, 150, 0]),
+            _ => panic!("expected RGBA data"),
+        }
+    }
+
+    #[test]
+    fn stencil_mask_paints_fill_color() {
+        let stream = Stream::new(dictionary! {
+            "Width" => 2, "Height" => 1, "ImageMask" => true,
+        }, vec![0b0100_0000]);
+        match DecodedImage::from_stream(&Document::new(), &stream, [1.0, 0.0, 0.0, 1.0]).unwrap().data {
+            ImageData::Rgba(rgba) => assert_eq!(rgba, [255, 0, 0, 255, 255, 0, 0, 0]),
+            _ => panic!("expected RGBA data"),
+        }
+    }
+}
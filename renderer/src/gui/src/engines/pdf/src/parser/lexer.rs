@@ -0,0 +1,537 @@
+// src/engines/pdf/src/parser/lexer.rs
+use std::collections::HashMap;
+use lopdf::{Dictionary, Object, StringFormat};
+
+/// An operand pushed onto the content stream operand stack.
+#[derive(Debug, Clone)]
+pub enum Operand {
+    Number(f32),
+    Boolean(bool),
+    Null,
+    Name(String),
+    String(Vec<u8>),
+    Array(Vec<Operand>),
+    Dictionary(HashMap<String, Operand>),
+}
+
+impl Operand {
+    pub fn as_number(&self) -> Option<f32> {
+        match self {
+            Operand::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_name(&self) -> Option<&str> {
+        match self {
+            Operand::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<&[u8]> {
+        match self {
+            Operand::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Operand]> {
+        match self {
+            Operand::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Converts the operand to the equivalent document object, e.g. for an
+    /// inline property list or image dictionary.
+    pub fn to_object(&self) -> Object {
+        match self {
+            Operand::Number(n) if n.fract() == 0.0 => Object::Integer(*n as i64),
+            Operand::Number(n) => Object::Real(*n),
+            Operand::Boolean(b) => Object::Boolean(*b),
+            Operand::Null => Object::Null,
+            Operand::Name(name) => Object::Name(name.as_bytes().to_vec()),
+            Operand::String(bytes) => Object::String(bytes.clone(), StringFormat::Literal),
+            Operand::Array(items) => Object::Array(items.iter().map(Operand::to_object).collect()),
+            Operand::Dictionary(entries) => {
+                let mut dict = Dictionary::new();
+                for (key, value) in entries {
+                    dict.set(key.as_str(), value.to_object());
+                }
+                Object::Dictionary(dict)
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Token {
+    Operand(Operand),
+    Keyword(String),
+}
+
+/// Splits a content stream into operands and operator keywords.
+pub struct Lexer<'a> {
+    data: &'a [u8],
+    position: usize,
+    // Byte offset of the first byte of the last token returned
+    token_start: usize,
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(b, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' | b'\0')
+}
+
+fn is_regular(b: u8) -> bool {
+    !is_whitespace(b) && !is_delimiter(b)
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Lexer { data, position: 0, token_start: 0 }
+    }
+
+    /// Byte offset at which the last token returned by `next_token` starts.
+    pub fn token_start(&self) -> usize {
+        self.token_start
+    }
+
+    pub fn next_token(&mut self) -> Option<Token> {
+        self.skip_whitespace_and_comments();
+        let start = self.position;
+
+        // Nested tokens of arrays and dictionaries overwrite the start, so
+        // it is set once the whole token has been read
+        let token = match self.peek_byte()? {
+            b'/' => Some(Token::Operand(Operand::Name(self.parse_name()))),
+            b'(' => Some(Token::Operand(self.parse_literal_string())),
+            b'[' => Some(Token::Operand(self.parse_array())),
+            b'<' if self.peek_byte_at(1) == Some(b'<') => Some(Token::Operand(self.parse_dictionary())),
+            b'<' => Some(Token::Operand(self.parse_hex_string())),
+            b'>' if self.peek_byte_at(1) == Some(b'>') => {
+                // Stray dictionary terminator
+                self.position += 2;
+                Some(Token::Keyword(">>".to_string()))
+            },
+            b')' | b']' | b'>' | b'{' | b'}' => {
+                let byte = self.peek_byte()?;
+                self.advance();
+                Some(Token::Keyword((byte as char).to_string()))
+            },
+            _ => Some(self.parse_regular()),
+        };
+        self.token_start = start;
+        token
+    }
+
+    /// Reads the data of an inline image following `ID`, leaving the lexer
+    /// after the closing `EI`. A known `length` is trusted when `EI`
+    /// follows it; otherwise the data ends at the first whitespace-delimited
+    /// `EI` followed by text that looks like content stream syntax.
+    pub fn read_inline_image_data(&mut self, length: Option<usize>) -> Vec<u8> {
+        // A single whitespace byte separates `ID` from the data
+        if self.peek_byte().is_some_and(is_whitespace) {
+            self.advance();
+        }
+        let start = self.position;
+
+        if let Some(length) = length {
+            let end = start.saturating_add(length).min(self.data.len());
+            let mut position = end;
+            while self.data.get(position).copied().is_some_and(is_whitespace) {
+                position += 1;
+            }
+            if self.is_end_marker(position) {
+                self.position = position + 2;
+                return self.data[start..end].to_vec();
+            }
+        }
+
+        for position in start..self.data.len() {
+            let preceded = position == start || is_whitespace(self.data[position - 1]);
+            if preceded && self.is_end_marker(position) && self.looks_like_content(position + 2) {
+                // Drop the whitespace separating the data from `EI`
+                let end = if position > start { position - 1 } else { position };
+                self.position = position + 2;
+                return self.data[start..end].to_vec();
+            }
+        }
+
+        self.position = self.data.len();
+        self.data[start..].to_vec()
+    }
+
+    fn is_end_marker(&self, position: usize) -> bool {
+        self.data[position.min(self.data.len())..].starts_with(b"EI")
+            && self.data.get(position + 2).is_none_or(|&b| !is_regular(b))
+    }
+
+    /// Whether the bytes at `position` plausibly continue a content stream,
+    /// used to reject `EI` sequences occurring inside binary image data.
+    fn looks_like_content(&self, position: usize) -> bool {
+        self.data[position.min(self.data.len())..]
+            .iter()
+            .take(32)
+            .all(|&b| b.is_ascii_graphic() || b.is_ascii_whitespace())
+    }
+
+    fn parse_regular(&mut self) -> Token {
+        let start = self.position;
+        while let Some(b) = self.peek_byte() {
+            if !is_regular(b) {
+                break;
+            }
+            self.advance();
+        }
+
+        let word = String::from_utf8_lossy(&self.data[start..self.position]).into_owned();
+        match word.as_str() {
+            "true" => Token::Operand(Operand::Boolean(true)),
+            "false" => Token::Operand(Operand::Boolean(false)),
+            "null" => Token::Operand(Operand::Null),
+            _ => match Self::parse_number(&word) {
+                Some(n) => Token::Operand(Operand::Number(n)),
+                None => Token::Keyword(word),
+            },
+        }
+    }
+
+    fn parse_number(word: &str) -> Option<f32> {
+        let first = *word.as_bytes().first()?;
+        if !(first.is_ascii_digit() || first == b'-' || first == b'+' || first == b'.') {
+            return None;
+        }
+        word.parse().ok()
+    }
+
+    fn parse_name(&mut self) -> String {
+        self.advance(); // Skip '/'
+
+        let mut name = Vec::new();
+        while let Some(b) = self.peek_byte() {
+            if !is_regular(b) {
+                break;
+            }
+            self.advance();
+            if b == b'#' {
+                let high = self.peek_byte().and_then(hex_value);
+                let low = self.peek_byte_at(1).and_then(hex_value);
+                if let (Some(high), Some(low)) = (high, low) {
+                    self.position += 2;
+                    name.push(high << 4 | low);
+                    continue;
+                }
+            }
+            name.push(b);
+        }
+
+        String::from_utf8_lossy(&name).into_owned()
+    }
+
+    fn parse_literal_string(&mut self) -> Operand {
+        self.advance(); // Skip '('
+        let mut bytes = Vec::new();
+        let mut depth = 0;
+
+        while let Some(b) = self.peek_byte() {
+            self.advance();
+            match b {
+                b'(' => {
+                    depth += 1;
+                    bytes.push(b);
+                },
+                b')' if depth == 0 => break,
+                b')' => {
+                    depth -= 1;
+                    bytes.push(b);
+                },
+                b'\\' => self.parse_escape(&mut bytes),
+                // An unescaped end-of-line is always read as a single newline
+                b'\r' => {
+                    if self.peek_byte() == Some(b'\n') {
+                        self.advance();
+                    }
+                    bytes.push(b'\n');
+                },
+                _ => bytes.push(b),
+            }
+        }
+
+        Operand::String(bytes)
+    }
+
+    fn parse_escape(&mut self, bytes: &mut Vec<u8>) {
+        let Some(b) = self.peek_byte() else {
+            return;
+        };
+        self.advance();
+
+        match b {
+            b'n' => bytes.push(b'\n'),
+            b'r' => bytes.push(b'\r'),
+            b't' => bytes.push(b'\t'),
+            b'b' => bytes.push(0x08),
+            b'f' => bytes.push(0x0c),
+            b'0'..=b'7' => {
+                // Up to three octal digits; overflow beyond a byte is ignored
+                let mut value = u32::from(b - b'0');
+                for _ in 0..2 {
+                    match self.peek_byte() {
+                        Some(digit @ b'0'..=b'7') => {
+                            value = value * 8 + u32::from(digit - b'0');
+                            self.advance();
+                        },
+                        _ => break,
+                    }
+                }
+                bytes.push(value as u8);
+            },
+            // Backslash at end of line continues the string on the next line
+            b'\r' => {
+                if self.peek_byte() == Some(b'\n') {
+                    self.advance();
+                }
+            },
+            b'\n' => {},
+            // Covers \(, \) and \\ as well as unknown escapes, whose
+            // backslash is dropped
+            _ => bytes.push(b),
+        }
+    }
+
+    fn parse_hex_string(&mut self) -> Operand {
+        self.advance(); // Skip '<'
+        let mut bytes = Vec::new();
+        let mut high: Option<u8> = None;
+
+        while let Some(b) = self.peek_byte() {
+            self.advance();
+            if b == b'>' {
+                break;
+            }
+            if let Some(value) = hex_value(b) {
+                match high.take() {
+                    Some(h) => bytes.push(h << 4 | value),
+                    None => high = Some(value),
+                }
+            }
+        }
+
+        // An odd number of digits behaves as if followed by a zero
+        if let Some(h) = high {
+            bytes.push(h << 4);
+        }
+
+        Operand::String(bytes)
+    }
+
+    fn parse_array(&mut self) -> Operand {
+        self.advance(); // Skip '['
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_whitespace_and_comments();
+            match self.peek_byte() {
+                None => break,
+                Some(b']') => {
+                    self.advance();
+                    break;
+                },
+                _ => match self.next_token() {
+                    Some(Token::Operand(operand)) => items.push(operand),
+                    // Operators are not allowed inside arrays; drop them
+                    Some(Token::Keyword(_)) => {},
+                    None => break,
+                },
+            }
+        }
+
+        Operand::Array(items)
+    }
+
+    fn parse_dictionary(&mut self) -> Operand {
+        self.position += 2; // Skip '<<'
+        let mut dict = HashMap::new();
+
+        loop {
+            self.skip_whitespace_and_comments();
+            match self.peek_byte() {
+                None => break,
+                Some(b'>') if self.peek_byte_at(1) == Some(b'>') => {
+                    self.position += 2;
+                    break;
+                },
+                Some(b'/') => {
+                    let key = self.parse_name();
+                    self.skip_whitespace_and_comments();
+                    if let Some(b'>') = self.peek_byte() {
+                        // Key without a value at the end of the dictionary
+                        continue;
+                    }
+                    match self.next_token() {
+                        Some(Token::Operand(value)) => {
+                            dict.insert(key, value);
+                        },
+                        Some(Token::Keyword(_)) => {},
+                        None => break,
+                    }
+                },
+                // Keys must be names; skip anything else
+                _ => {
+                    self.next_token();
+                },
+            }
+        }
+
+        Operand::Dictionary(dict)
+    }
+
+    fn peek_byte(&self) -> Option<u8> {
+        self.data.get(self.position).copied()
+    }
+
+    fn peek_byte_at(&self, offset: usize) -> Option<u8> {
+        self.data.get(self.position + offset).copied()
+    }
+
+    fn advance(&mut self) {
+        self.position += 1;
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(b) = self.peek_byte() {
+            if b == b'%' {
+                while let Some(b) = self.peek_byte() {
+                    if b == b'\r' || b == b'\n' {
+                        break;
+                    }
+                    self.advance();
+                }
+            } else if is_whitespace(b) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(data: &[u8]) -> Vec<Token> {
+        let mut lexer = Lexer::new(data);
+        std::iter::from_fn(|| lexer.next_token()).collect()
+    }
+
+    fn string(token: &Token) -> &[u8] {
+        match token {
+            Token::Operand(Operand::String(bytes)) => bytes,
+            other => panic!("expected a string, got {:?}", other),
+        }
+    }
+
+    fn keyword(token: &Token) -> &str {
+        match token {
+            Token::Keyword(keyword) => keyword,
+            other => panic!("expected a keyword, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn literal_string_escapes() {
+        let tokens = tokens(b"(a\\n\\t\\(b\\)\\\\) (\\101\\0053\\7) (nested (parens) kept) (line\\\ncontinued) (end\r\nof line)");
+        assert_eq!(string(&tokens[0]), b"a\n\t(b)\\");
+        assert_eq!(string(&tokens[1]), b"A\x053\x07");
+        assert_eq!(string(&tokens[2]), b"nested (parens) kept");
+        assert_eq!(string(&tokens[3]), b"linecontinued");
+        assert_eq!(string(&tokens[4]), b"end\nof line");
+    }
+
+    #[test]
+    fn hex_strings_and_names() {
+        let tokens = tokens(b"<48 65 6C6c 6F7> /A#20B /F1 Tf");
+        assert_eq!(string(&tokens[0]), b"Hello\x70");
+        assert!(matches!(&tokens[1], Token::Operand(Operand::Name(name)) if name == "A B"));
+        assert!(matches!(&tokens[2], Token::Operand(Operand::Name(name)) if name == "F1"));
+        assert_eq!(keyword(&tokens[3]), "Tf");
+    }
+
+    #[test]
+    fn numbers_arrays_and_dictionaries() {
+        let tokens = tokens(b"-1.5 .25 +3 [1 (a) /N] << /K true /V [2] >> null");
+        assert!(matches!(tokens[0], Token::Operand(Operand::Number(n)) if n == -1.5));
+        assert!(matches!(tokens[1], Token::Operand(Operand::Number(n)) if n == 0.25));
+        assert!(matches!(tokens[2], Token::Operand(Operand::Number(n)) if n == 3.0));
+        match &tokens[3] {
+            Token::Operand(Operand::Array(items)) => assert_eq!(items.len(), 3),
+            other => panic!("expected an array, got {:?}", other),
+        }
+        match &tokens[4] {
+            Token::Operand(Operand::Dictionary(entries)) => {
+                assert!(matches!(entries.get("K"), Some(Operand::Boolean(true))));
+                assert!(entries.get("V").and_then(Operand::as_array).is_some());
+            },
+            other => panic!("expected a dictionary, got {:?}", other),
+        }
+        assert!(matches!(tokens[5], Token::Operand(Operand::Null)));
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        let tokens = tokens(b"% comment\nq % another\nQ");
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(keyword(&tokens[0]), "q");
+        assert_eq!(keyword(&tokens[1]), "Q");
+    }
+
+    #[test]
+    fn token_start_counts_bytes() {
+        let mut lexer = Lexer::new(b"\x80\xFF q [1 2] re");
+        assert_eq!(keyword(&lexer.next_token().unwrap()), "\u{FFFD}\u{FFFD}");
+        assert_eq!(lexer.token_start(), 0);
+        lexer.next_token();
+        assert_eq!(lexer.token_start(), 3);
+        lexer.next_token();
+        assert_eq!(lexer.token_start(), 5);
+        lexer.next_token();
+        assert_eq!(lexer.token_start(), 11);
+    }
+
+    #[test]
+    fn inline_image_data_ends_at_content_like_ei() {
+        // `EI` inside the data is followed by binary bytes, not content
+        let mut lexer = Lexer::new(b"ID \x01 EI\x00\xFF\x02 EI Q");
+        lexer.next_token();
+        assert_eq!(lexer.read_inline_image_data(None), b"\x01 EI\x00\xFF\x02");
+        assert_eq!(keyword(&lexer.next_token().unwrap()), "Q");
+    }
+
+    #[test]
+    fn inline_image_data_trusts_a_length_followed_by_ei() {
+        let mut lexer = Lexer::new(b"ID EI EI\nQ");
+        lexer.next_token();
+        assert_eq!(lexer.read_inline_image_data(Some(2)), b"EI");
+        assert_eq!(keyword(&lexer.next_token().unwrap()), "Q");
+
+        // A huge declared length falls back to scanning
+        let mut lexer = Lexer::new(b"ID ab EI Q");
+        lexer.next_token();
+        assert_eq!(lexer.read_inline_image_data(Some(usize::MAX)), b"ab");
+    }
+}
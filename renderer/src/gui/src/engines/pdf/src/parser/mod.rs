@@ -1,11 +1,27 @@
 // src/engines/pdf/src/parser/mod.rs
 pub mod content;  // New module for content stream parsing
 pub mod font;     // Font-specific parsing
+pub mod font_program; // Embedded font program extraction
+pub mod encoding; // Simple font encodings and glyph names
+pub mod glyph_list; // The Adobe Glyph List
+pub mod cmap;     // CMaps for code splitting and ToUnicode
 pub mod color;    // Color space parsing
+pub mod lexer;    // Content stream tokenizer
+pub mod ext_gstate; // Graphics state parameter dictionaries
+pub mod filters;  // Stream filter decoding
+pub mod image;    // Image XObject decoding
+pub mod optional_content; // Layers from /OCProperties
+pub mod function; // PDF function evaluation
+pub mod shading;  // Shadings and gradient decoding
+pub mod diagnostics; // Problems reported while parsing
 
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use lopdf::{Document, Dictionary, Object, ObjectId};
 use font::PDFFont;
+use color::ColorSpace;
+use ext_gstate::ExtGState;
+use diagnostics::{DiagnosticKind, Diagnostics, Severity};
 use wasm_bindgen::JsValue;
 
 #[derive(Debug)]
@@ -27,39 +43,243 @@ impl From<lopdf::Error> for PDFError {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum FontType {
+    Type0,
     Type1,
+    MMType1,
     TrueType,
+    CIDFontType0,
     CIDFontType2,
     Type3,
 }
 
+impl FontType {
+    /// Maps a font dictionary's `/Subtype`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Type0" => Some(FontType::Type0),
+            "Type1" => Some(FontType::Type1),
+            "MMType1" => Some(FontType::MMType1),
+            "TrueType" => Some(FontType::TrueType),
+            "CIDFontType0" => Some(FontType::CIDFontType0),
+            "CIDFontType2" => Some(FontType::CIDFontType2),
+            "Type3" => Some(FontType::Type3),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FontType::Type0 => "Type0",
+            FontType::Type1 => "Type1",
+            FontType::MMType1 => "MMType1",
+            FontType::TrueType => "TrueType",
+            FontType::CIDFontType0 => "CIDFontType0",
+            FontType::CIDFontType2 => "CIDFontType2",
+            FontType::Type3 => "Type3",
+        }
+    }
+}
+
+#[derive(Default)]
 pub struct PDFResources {
     fonts: HashMap<String, PDFFont>,
+    font_ids: HashMap<ObjectId, String>,
+    color_spaces: HashMap<String, ColorSpace>,
+    ext_gstates: HashMap<String, ExtGState>,
+    xobjects: HashMap<String, ObjectId>,
+    properties: HashMap<String, Object>,
+    shadings: HashMap<String, Object>,
+    patterns: HashMap<String, Object>,
+    /// Problems with individual resources, which are skipped.
+    diagnostics: Diagnostics,
+}
+
+/// Follows an indirect reference, returning other objects unchanged.
+pub fn resolve<'a>(doc: &'a Document, object: &'a Object) -> &'a Object {
+    match object {
+        Object::Reference(id) => doc.get_object(*id).unwrap_or(object),
+        _ => object,
+    }
+}
+
+/// Looks up a resource category such as `/ColorSpace`, which may be given
+/// directly or by reference.
+fn resource_dict<'a>(doc: &'a Document, resources: &'a Dictionary, key: &[u8]) -> Option<&'a Dictionary> {
+    match resources.get(key).ok()? {
+        Object::Reference(id) => doc.get_dictionary(*id).ok(),
+        Object::Dictionary(dict) => Some(dict),
+        _ => None,
+    }
 }
 
 impl PDFResources {
     pub fn new(doc: &Document, resources: &Dictionary) -> Result<Self, PDFError> {
         let mut fonts = HashMap::new();
+        let mut font_ids = HashMap::new();
+        let mut diagnostics = Diagnostics::default();
         
-        if let Ok(font_dict) = resources.get(b"Font") {
-            if let Object::Dictionary(dict) = font_dict {
-                for (name, font_ref) in dict.iter() {
-                    if let Object::Reference(ref_id) = font_ref {
-                        if let Ok(font_dict) = doc.get_dictionary(*ref_id) {
-                            let font = PDFFont::from_dictionary(doc, font_dict)?;
-                            fonts.insert(String::from_utf8_lossy(name).into_owned(), font);
+        // Font dictionaries are usually indirect, but may be given inline
+        if let Some(dict) = resource_dict(doc, resources, b"Font") {
+            for (name, object) in dict.iter() {
+                let name = String::from_utf8_lossy(name).into_owned();
+                // A font that cannot be used leaves its text unresolved
+                // rather than failing the page
+                let font = match resolve(doc, object).as_dict() {
+                    Ok(font_dict) => PDFFont::from_dictionary(doc, font_dict),
+                    Err(e) => Err(PDFError::from(e)),
+                };
+                let font = match font {
+                    Ok(font) => font,
+                    Err(e) => {
+                        diagnostics.report(DiagnosticKind::DecodeFailed, Severity::Warning, format!(
+                            "Skipping font /{}: {:?}", name, e
+                        ));
+                        continue;
+                    },
+                };
+                if let Some(cmap) = font.unavailable_cmap() {
+                    diagnostics.report(DiagnosticKind::Unsupported, Severity::Warning, format!(
+                        "Font /{} uses the predefined CMap {}, whose CIDs are not available", name, cmap
+                    ));
+                }
+                if let Object::Reference(id) = object {
+                    font_ids.insert(*id, name.clone());
+                }
+                fonts.insert(name, font);
+            }
+        }
+        
+        let mut color_spaces = HashMap::new();
+        if let Some(dict) = resource_dict(doc, resources, b"ColorSpace") {
+            for (name, object) in dict.iter() {
+                if let Some(space) = ColorSpace::from_object(doc, object) {
+                    color_spaces.insert(String::from_utf8_lossy(name).into_owned(), space);
+                }
+            }
+        }
+
+        let mut ext_gstates = HashMap::new();
+        if let Some(dict) = resource_dict(doc, resources, b"ExtGState") {
+            for (name, object) in dict.iter() {
+                if let Ok(gs_dict) = resolve(doc, object).as_dict() {
+                    let ext_gstate = ExtGState::from_dictionary(doc, gs_dict);
+
+                    // Fonts selected only through `gs` are not in `/Font`, so
+                    // load them under a name derived from their reference
+                    if let Some((id, _)) = ext_gstate.font {
+                        if let (Entry::Vacant(entry), Ok(font_dict)) = (font_ids.entry(id), doc.get_dictionary(id)) {
+                            let font_name = format!("{} {} R", id.0, id.1);
+                            match PDFFont::from_dictionary(doc, font_dict) {
+                                Ok(font) => {
+                                    if let Some(cmap) = font.unavailable_cmap() {
+                                        diagnostics.report(DiagnosticKind::Unsupported, Severity::Warning, format!(
+                                            "Font {} uses the predefined CMap {}, whose CIDs are not available",
+                                            font_name, cmap
+                                        ));
+                                    }
+                                    fonts.insert(font_name.clone(), font);
+                                    entry.insert(font_name);
+                                },
+                                Err(e) => diagnostics.report(DiagnosticKind::DecodeFailed, Severity::Warning, format!(
+                                    "Skipping font {} of graphics state /{}: {:?}",
+                                    font_name, String::from_utf8_lossy(name), e
+                                )),
+                            }
                         }
+                    } else if gs_dict.has(b"Font") {
+                        // Only fonts given by reference can be named and loaded
+                        diagnostics.report(DiagnosticKind::Unsupported, Severity::Warning, format!(
+                            "Ignoring /Font of graphics state /{}, which is not a font reference and size",
+                            String::from_utf8_lossy(name)
+                        ));
                     }
+
+                    ext_gstates.insert(String::from_utf8_lossy(name).into_owned(), ext_gstate);
                 }
             }
         }
-        
-        Ok(PDFResources { fonts })
+
+        // XObjects are resolved when drawn, since forms may refer to themselves
+        let mut xobjects = HashMap::new();
+        if let Some(dict) = resource_dict(doc, resources, b"XObject") {
+            for (name, object) in dict.iter() {
+                if let Object::Reference(id) = object {
+                    xobjects.insert(String::from_utf8_lossy(name).into_owned(), *id);
+                }
+            }
+        }
+
+        // Property lists keep references, which identify optional content
+        // groups. Shadings and patterns are decoded only when used.
+        let entries = |key: &[u8]| -> HashMap<String, Object> {
+            resource_dict(doc, resources, key)
+                .map(|dict| dict.iter()
+                    .map(|(name, object)| (String::from_utf8_lossy(name).into_owned(), object.clone()))
+                    .collect())
+                .unwrap_or_default()
+        };
+        let properties = entries(b"Properties");
+        let shadings = entries(b"Shading");
+        let patterns = entries(b"Pattern");
+
+        Ok(PDFResources {
+            fonts,
+            font_ids,
+            color_spaces,
+            ext_gstates,
+            xobjects,
+            properties,
+            shadings,
+            patterns,
+            diagnostics,
+        })
+    }
+
+    /// Moves out the problems found while loading the resources.
+    pub fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
     }
 
     pub fn get_font(&self, name: &str) -> Result<Option<&PDFFont>, PDFError> {
         Ok(self.fonts.get(name))
     }
+
+    /// The page's fonts with their resource names, in no particular order.
+    pub fn fonts(&self) -> impl Iterator<Item = (&str, &PDFFont)> {
+        self.fonts.iter().map(|(name, font)| (name.as_str(), font))
+    }
+
+    /// Resource name of the font loaded from the dictionary at `id`.
+    pub fn font_name(&self, id: ObjectId) -> Option<&str> {
+        self.font_ids.get(&id).map(String::as_str)
+    }
+
+    pub fn get_ext_gstate(&self, name: &str) -> Option<&ExtGState> {
+        self.ext_gstates.get(name)
+    }
+
+    pub fn get_xobject(&self, name: &str) -> Option<ObjectId> {
+        self.xobjects.get(name).copied()
+    }
+
+    /// A named property list, as a reference or direct dictionary.
+    pub fn get_properties(&self, name: &str) -> Option<&Object> {
+        self.properties.get(name)
+    }
+
+    pub fn get_shading(&self, name: &str) -> Option<&Object> {
+        self.shadings.get(name)
+    }
+
+    pub fn get_pattern(&self, name: &str) -> Option<&Object> {
+        self.patterns.get(name)
+    }
+
+    /// Resolves a color space operand: a device family name or the name of
+    /// an entry in the `/ColorSpace` resource.
+    pub fn get_color_space(&self, name: &str) -> Option<ColorSpace> {
+        ColorSpace::from_name(name).or_else(|| self.color_spaces.get(name).cloned())
+    }
 }
\ No newline at end of file
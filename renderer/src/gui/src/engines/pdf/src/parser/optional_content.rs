@@ -0,0 +1,176 @@
+// src/engines/pdf/src/parser/optional_content.rs
+use std::collections::HashSet;
+use lopdf::{Document, Dictionary, Object, ObjectId};
+use super::resolve;
+
+/// Nesting limit for `/VE` visibility expressions.
+const MAX_EXPRESSION_DEPTH: usize = 16;
+
+/// An optional content group, shown to users as a layer.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub id: ObjectId,
+    pub name: String,
+    pub visible: bool,
+}
+
+/// Optional content groups of a document with their current visibility,
+/// initialised from the default configuration in `/OCProperties`.
+#[derive(Debug, Clone, Default)]
+pub struct OptionalContent {
+    layers: Vec<Layer>,
+}
+
+impl OptionalContent {
+    pub fn from_document(doc: &Document) -> Self {
+        let properties = doc.catalog().ok()
+            .and_then(|catalog| catalog.get(b"OCProperties").ok())
+            .and_then(|o| resolve(doc, o).as_dict().ok());
+        let Some(properties) = properties else {
+            return OptionalContent::default();
+        };
+
+        let config = properties.get(b"D").ok().and_then(|o| resolve(doc, o).as_dict().ok());
+        let config_ids = |key: &[u8]| -> HashSet<ObjectId> {
+            config.and_then(|d| d.get(key).ok())
+                .and_then(|o| resolve(doc, o).as_array().ok())
+                .map(|a| a.iter().filter_map(|o| o.as_reference().ok()).collect())
+                .unwrap_or_default()
+        };
+        let base_on = config.and_then(|d| d.get(b"BaseState").ok())
+            .and_then(|o| o.as_name_str().ok())
+            .is_none_or(|state| state != "OFF");
+        let on = config_ids(b"ON");
+        let off = config_ids(b"OFF");
+
+        let layers = properties.get(b"OCGs").ok()
+            .and_then(|o| resolve(doc, o).as_array().ok())
+            .map(|groups| groups.iter()
+                .filter_map(|o| o.as_reference().ok())
+                .map(|id| Layer {
+                    id,
+                    name: Self::group_name(doc, id),
+                    visible: if on.contains(&id) {
+                        true
+                    } else if off.contains(&id) {
+                        false
+                    } else {
+                        base_on
+                    },
+                })
+                .collect())
+            .unwrap_or_default();
+
+        OptionalContent { layers }
+    }
+
+    fn group_name(doc: &Document, id: ObjectId) -> String {
+        let name = doc.get_dictionary(id).ok()
+            .and_then(|group| group.get(b"Name").ok())
+            .and_then(|o| match resolve(doc, o) {
+                Object::String(bytes, _) => Some(decode_text_string(bytes)),
+                _ => None,
+            });
+        name.unwrap_or_else(|| format!("{} {} R", id.0, id.1))
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// Sets the visibility of the layer at `index`, returning `false` if
+    /// there is no such layer.
+    pub fn set_visible(&mut self, index: usize, visible: bool) -> bool {
+        match self.layers.get_mut(index) {
+            Some(layer) => {
+                layer.visible = visible;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Whether content whose `/OC` entry is `object` (a group or membership
+    /// dictionary) should be drawn.
+    pub fn is_visible(&self, doc: &Document, object: &Object) -> bool {
+        let id = object.as_reference().ok();
+        let Ok(dict) = resolve(doc, object).as_dict() else {
+            return true;
+        };
+
+        match dict.get(b"Type").and_then(Object::as_name_str) {
+            Ok("OCMD") => self.membership_visible(doc, dict),
+            _ => id.is_none_or(|id| self.group_visible(id)),
+        }
+    }
+
+    /// Groups missing from `/OCGs` are treated as visible.
+    fn group_visible(&self, id: ObjectId) -> bool {
+        self.layers.iter()
+            .find(|layer| layer.id == id)
+            .is_none_or(|layer| layer.visible)
+    }
+
+    fn membership_visible(&self, doc: &Document, dict: &Dictionary) -> bool {
+        // A visibility expression takes precedence over `/OCGs` and `/P`
+        if let Ok(expression) = dict.get(b"VE") {
+            return self.expression_visible(doc, expression, 0);
+        }
+
+        let groups: Vec<bool> = match dict.get(b"OCGs").map(|o| (o, resolve(doc, o))) {
+            Ok((_, Object::Array(groups))) => groups.iter()
+                .filter_map(|o| o.as_reference().ok())
+                .map(|id| self.group_visible(id))
+                .collect(),
+            Ok((Object::Reference(id), _)) => vec![self.group_visible(*id)],
+            _ => Vec::new(),
+        };
+        if groups.is_empty() {
+            return true;
+        }
+
+        match dict.get(b"P").and_then(Object::as_name_str) {
+            Ok("AllOn") => groups.iter().all(|&on| on),
+            Ok("AnyOff") => groups.iter().any(|&on| !on),
+            Ok("AllOff") => groups.iter().all(|&on| !on),
+            _ => groups.iter().any(|&on| on),
+        }
+    }
+
+    fn expression_visible(&self, doc: &Document, expression: &Object, depth: usize) -> bool {
+        if depth > MAX_EXPRESSION_DEPTH {
+            return true;
+        }
+        if let Object::Reference(id) = expression {
+            if !matches!(resolve(doc, expression), Object::Array(_)) {
+                return self.group_visible(*id);
+            }
+        }
+        let Ok(array) = resolve(doc, expression).as_array() else {
+            return true;
+        };
+
+        let mut operands = array.iter().skip(1)
+            .map(|operand| self.expression_visible(doc, operand, depth + 1));
+        match array.first().and_then(|o| o.as_name_str().ok()) {
+            Some("And") => operands.all(|on| on),
+            Some("Or") => operands.any(|on| on),
+            Some("Not") => !operands.next().unwrap_or(false),
+            _ => true,
+        }
+    }
+}
+
+/// Decodes a PDF text string, which is either UTF-16BE with a byte order
+/// mark or PDFDocEncoding (approximated here by Latin-1).
+fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        },
+        _ => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
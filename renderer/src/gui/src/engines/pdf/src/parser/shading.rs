@@ -0,0 +1,425 @@
+// src/engines/pdf/src/parser/shading.rs
+use lopdf::{Document, Dictionary, Object};
+use super::{PDFError, resolve};
+use super::color::ColorSpace;
+use super::function::Function;
+use super::filters::decode_stream;
+use crate::content::{Shading, ShadingGeometry, GradientStop, ShadingVertex, Matrix};
+
+/// Samples taken along the parametric variable of axial and radial shadings.
+const GRADIENT_STOPS: usize = 32;
+
+/// Cells per side of the grid approximating a function-based shading.
+const FUNCTION_GRID: usize = 16;
+
+/// Cells per side of the grid approximating each Coons or tensor patch.
+const PATCH_GRID: usize = 8;
+
+/// Positions in the 4×4 tensor control grid of the twelve boundary points,
+/// in the order they appear in patch mesh data.
+const BOUNDARY: [(usize, usize); 12] = [
+    (0, 0), (0, 1), (0, 2), (0, 3), (1, 3), (2, 3),
+    (3, 3), (3, 2), (3, 1), (3, 0), (2, 0), (1, 0),
+];
+
+/// Positions of the four interior points of a tensor-product patch.
+const INTERIOR: [(usize, usize); 4] = [(1, 1), (1, 2), (2, 2), (2, 1)];
+
+/// Color space and functions shared by all shading types. With functions,
+/// colors are given by a single parametric value passed through them.
+struct ShadingColor {
+    space: ColorSpace,
+    functions: Vec<Function>,
+}
+
+impl ShadingColor {
+    fn color(&self, inputs: &[f32]) -> [f32; 4] {
+        if self.functions.is_empty() {
+            return self.space.color_from_components(inputs).to_rgba(1.0);
+        }
+        let components: Vec<f32> = self.functions.iter()
+            .flat_map(|function| function.evaluate(inputs))
+            .collect();
+        self.space.color_from_components(&components).to_rgba(1.0)
+    }
+
+    /// Number of color values stored per mesh vertex.
+    fn components(&self) -> usize {
+        if self.functions.is_empty() {
+            self.space.components()
+        } else {
+            1
+        }
+    }
+}
+
+/// A mesh vertex before color conversion.
+#[derive(Debug, Clone)]
+struct RawVertex {
+    x: f32,
+    y: f32,
+    components: Vec<f32>,
+}
+
+/// Boundary points and corner colors of a patch, which the next patch may
+/// continue from.
+type PatchEdges = ([(f32, f32); 12], [Vec<f32>; 4]);
+
+/// Parses a shading dictionary or stream. `matrix` maps shading space to
+/// user space: the CTM for `sh`, or the pattern matrix for patterns.
+pub fn parse_shading(doc: &Document, object: &Object, matrix: Matrix) -> Result<Shading, PDFError> {
+    let (dict, stream) = match resolve(doc, object) {
+        Object::Dictionary(dict) => (dict, None),
+        Object::Stream(stream) => (&stream.dict, Some(stream)),
+        _ => return Err(PDFError::ParseError("Shading is not a dictionary or stream".to_string())),
+    };
+    let numbers = |key: &[u8]| numbers(doc, dict, key);
+
+    let space = dict.get(b"ColorSpace").ok()
+        .and_then(|o| ColorSpace::from_object(doc, o))
+        .ok_or_else(|| PDFError::ParseError("Shading without a valid ColorSpace".to_string()))?;
+    let functions = match dict.get(b"Function").map(|o| resolve(doc, o)) {
+        Ok(Object::Array(functions)) => functions.iter()
+            .map(|f| Function::from_object(doc, f))
+            .collect::<Result<Vec<_>, _>>()?,
+        Ok(function) => vec![Function::from_object(doc, function)?],
+        Err(_) => Vec::new(),
+    };
+    let color = ShadingColor { space, functions };
+
+    let background = numbers(b"Background").map(|components| color.space.color_from_components(&components).to_rgba(1.0));
+    let bbox = match numbers(b"BBox").as_deref() {
+        Some(&[x0, y0, x1, y1]) => Some([x0, y0, x1, y1]),
+        _ => None,
+    };
+    let anti_alias = dict.get(b"AntiAlias").and_then(Object::as_bool).unwrap_or(false);
+
+    let shading_type = dict.get(b"ShadingType").and_then(Object::as_i64)
+        .map_err(|_| PDFError::ParseError("Shading without ShadingType".to_string()))?;
+    let geometry = match (shading_type, stream) {
+        (1, _) => function_based(doc, dict, &color),
+        (2 | 3, _) => {
+            let coords = numbers(b"Coords").unwrap_or_default();
+            let domain = numbers(b"Domain").unwrap_or_else(|| vec![0.0, 1.0]);
+            let (t0, t1) = (domain.first().copied().unwrap_or(0.0), domain.get(1).copied().unwrap_or(1.0));
+            let stops = (0..=GRADIENT_STOPS).map(|i| {
+                let offset = i as f32 / GRADIENT_STOPS as f32;
+                GradientStop { offset, color: color.color(&[t0 + offset * (t1 - t0)]) }
+            }).collect();
+            let extend = match dict.get(b"Extend").ok().and_then(|o| resolve(doc, o).as_array().ok()).map(Vec::as_slice) {
+                Some([start, end]) => (start.as_bool().unwrap_or(false), end.as_bool().unwrap_or(false)),
+                _ => (false, false),
+            };
+
+            match (shading_type, &coords[..]) {
+                (2, &[x0, y0, x1, y1]) => ShadingGeometry::Axial { start: (x0, y0), end: (x1, y1), stops, extend },
+                (3, &[x0, y0, r0, x1, y1, r1]) => ShadingGeometry::Radial {
+                    start: (x0, y0, r0),
+                    end: (x1, y1, r1),
+                    stops,
+                    extend,
+                },
+                _ => return Err(PDFError::ParseError("Shading with invalid Coords".to_string())),
+            }
+        },
+        (4..=7, Some(stream)) => {
+            let reader = MeshReader::new(doc, dict, decode_stream(stream)?, &color);
+            let triangles = match shading_type {
+                4 => reader.free_form_triangles(),
+                5 => {
+                    let per_row = dict.get(b"VerticesPerRow").and_then(Object::as_i64).unwrap_or(2).max(2);
+                    reader.lattice_triangles(per_row as usize)
+                },
+                _ => reader.patch_triangles(shading_type == 7),
+            };
+            ShadingGeometry::Mesh(triangles.into_iter()
+                .map(|triangle| triangle.map(|v| ShadingVertex { x: v.x, y: v.y, color: color.color(&v.components) }))
+                .collect())
+        },
+        (other, _) => return Err(PDFError::ParseError(format!("Unsupported shading type {}", other))),
+    };
+
+    Ok(Shading { geometry, matrix, bbox, background, anti_alias })
+}
+
+fn numbers(doc: &Document, dict: &Dictionary, key: &[u8]) -> Option<Vec<f32>> {
+    dict.get(key).ok()
+        .and_then(|o| resolve(doc, o).as_array().ok())
+        .map(|a| a.iter().filter_map(|n| n.as_float().ok()).collect())
+}
+
+/// Samples a type 1 shading's function over its domain as a triangle grid.
+fn function_based(doc: &Document, dict: &Dictionary, color: &ShadingColor) -> ShadingGeometry {
+    let domain = match numbers(doc, dict, b"Domain").as_deref() {
+        Some(&[x0, x1, y0, y1]) => [x0, x1, y0, y1],
+        _ => [0.0, 1.0, 0.0, 1.0],
+    };
+    let matrix = match numbers(doc, dict, b"Matrix").as_deref() {
+        Some(&[a, b, c, d, e, f]) => Matrix::new(a, b, c, d, e, f),
+        _ => Matrix::default(),
+    };
+
+    let vertex = |i: usize, j: usize| {
+        let u = domain[0] + (domain[1] - domain[0]) * i as f32 / FUNCTION_GRID as f32;
+        let v = domain[2] + (domain[3] - domain[2]) * j as f32 / FUNCTION_GRID as f32;
+        let (x, y) = matrix.transform_point(u, v);
+        ShadingVertex { x, y, color: color.color(&[u, v]) }
+    };
+    let grid: Vec<Vec<ShadingVertex>> = (0..=FUNCTION_GRID)
+        .map(|i| (0..=FUNCTION_GRID).map(|j| vertex(i, j)).collect())
+        .collect();
+    ShadingGeometry::Mesh(grid_triangles(&grid))
+}
+
+/// Splits each cell of a vertex grid into two triangles.
+fn grid_triangles<T: Clone>(grid: &[Vec<T>]) -> Vec<[T; 3]> {
+    let mut triangles = Vec::new();
+    for (row, next) in grid.iter().zip(grid.iter().skip(1)) {
+        for j in 0..row.len().min(next.len()).saturating_sub(1) {
+            triangles.push([row[j].clone(), row[j + 1].clone(), next[j].clone()]);
+            triangles.push([row[j + 1].clone(), next[j + 1].clone(), next[j].clone()]);
+        }
+    }
+    triangles
+}
+
+/// Reads vertices and flags from the packed data of mesh shadings
+/// (types 4 to 7).
+struct MeshReader {
+    data: Vec<u8>,
+    /// Position in bits.
+    position: usize,
+    bits_per_coordinate: usize,
+    bits_per_component: usize,
+    bits_per_flag: usize,
+    decode: Vec<f32>,
+    components: usize,
+}
+
+impl MeshReader {
+    fn new(doc: &Document, dict: &Dictionary, data: Vec<u8>, color: &ShadingColor) -> Self {
+        let bits = |key: &[u8]| dict.get(key).and_then(Object::as_i64).unwrap_or(8).clamp(1, 32) as usize;
+        MeshReader {
+            data,
+            position: 0,
+            bits_per_coordinate: bits(b"BitsPerCoordinate"),
+            bits_per_component: bits(b"BitsPerComponent"),
+            bits_per_flag: bits(b"BitsPerFlag"),
+            decode: numbers(doc, dict, b"Decode").unwrap_or_default(),
+            components: color.components(),
+        }
+    }
+
+    fn read_bits(&mut self, count: usize) -> Option<u32> {
+        if self.position + count > self.data.len() * 8 {
+            return None;
+        }
+        let mut value: u64 = 0;
+        for _ in 0..count {
+            let byte = self.data[self.position / 8];
+            value = value << 1 | u64::from(byte >> (7 - self.position % 8) & 1);
+            self.position += 1;
+        }
+        Some(value as u32)
+    }
+
+    /// Skips to the next byte boundary.
+    fn align(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+
+    /// Reads a value and maps it through decode range `index`.
+    fn read_decoded(&mut self, bits: usize, index: usize) -> Option<f32> {
+        let raw = self.read_bits(bits)?;
+        let max = ((1u64 << bits) - 1) as f32;
+        let low = self.decode.get(index * 2).copied().unwrap_or(0.0);
+        let high = self.decode.get(index * 2 + 1).copied().unwrap_or(1.0);
+        Some(low + raw as f32 * (high - low) / max)
+    }
+
+    fn read_point(&mut self) -> Option<(f32, f32)> {
+        let x = self.read_decoded(self.bits_per_coordinate, 0)?;
+        let y = self.read_decoded(self.bits_per_coordinate, 1)?;
+        Some((x, y))
+    }
+
+    fn read_color(&mut self) -> Option<Vec<f32>> {
+        (0..self.components)
+            .map(|i| self.read_decoded(self.bits_per_component, 2 + i))
+            .collect()
+    }
+
+    fn read_vertex(&mut self) -> Option<RawVertex> {
+        let (x, y) = self.read_point()?;
+        let components = self.read_color()?;
+        Some(RawVertex { x, y, components })
+    }
+
+    /// Type 4: each vertex's flag says whether it starts a new triangle or
+    /// extends the previous one from its last two (1) or first and last (2)
+    /// vertices.
+    fn free_form_triangles(mut self) -> Vec<[RawVertex; 3]> {
+        let mut triangles: Vec<[RawVertex; 3]> = Vec::new();
+        let mut pending: Vec<RawVertex> = Vec::new();
+
+        while let Some(flag) = self.read_bits(self.bits_per_flag) {
+            let Some(vertex) = self.read_vertex() else {
+                break;
+            };
+            self.align();
+
+            let previous = triangles.last().cloned();
+            match (flag, previous) {
+                (1, Some([_, b, c])) if pending.is_empty() => triangles.push([b, c, vertex]),
+                (2, Some([a, _, c])) if pending.is_empty() => triangles.push([a, c, vertex]),
+                _ => {
+                    // Flags of the second and third vertices of a new
+                    // triangle are ignored
+                    pending.push(vertex);
+                    if pending.len() == 3 {
+                        let mut vertices = pending.drain(..);
+                        if let (Some(a), Some(b), Some(c)) = (vertices.next(), vertices.next(), vertices.next()) {
+                            triangles.push([a, b, c]);
+                        }
+                    }
+                },
+            }
+        }
+        triangles
+    }
+
+    /// Type 5: vertices form a grid of `per_row` columns.
+    fn lattice_triangles(mut self, per_row: usize) -> Vec<[RawVertex; 3]> {
+        let mut vertices = Vec::new();
+        while let Some(vertex) = self.read_vertex() {
+            vertices.push(vertex);
+        }
+        let rows: Vec<Vec<RawVertex>> = vertices.chunks_exact(per_row).map(<[RawVertex]>::to_vec).collect();
+        grid_triangles(&rows)
+    }
+
+    /// Types 6 and 7: Coons patches are converted to tensor-product patches
+    /// and each is approximated by a triangle grid.
+    fn patch_triangles(mut self, tensor: bool) -> Vec<[RawVertex; 3]> {
+        let mut triangles = Vec::new();
+        let mut previous: Option<PatchEdges> = None;
+
+        while let Some(flag) = self.read_bits(self.bits_per_flag) {
+            let mut boundary = [(0.0, 0.0); 12];
+            let mut colors: [Vec<f32>; 4] = Default::default();
+
+            // Flags 1 to 3 continue from an edge of the previous patch
+            let shared = match (flag, &previous) {
+                (1..=3, Some((points, corners))) => {
+                    let edge = flag as usize;
+                    for (i, point) in boundary.iter_mut().take(4).enumerate() {
+                        *point = points[(edge * 3 + i) % 12];
+                    }
+                    colors[0] = corners[edge].clone();
+                    colors[1] = corners[(edge + 1) % 4].clone();
+                    true
+                },
+                _ => false,
+            };
+
+            let (first_point, first_color) = if shared { (4, 2) } else { (0, 0) };
+            let read_points = boundary[first_point..].iter_mut()
+                .try_for_each(|point| {
+                    *point = self.read_point()?;
+                    Some(())
+                });
+            let mut interior = [(0.0, 0.0); 4];
+            let read_interior = if tensor {
+                interior.iter_mut().try_for_each(|point| {
+                    *point = self.read_point()?;
+                    Some(())
+                })
+            } else {
+                Some(())
+            };
+            let read_colors = colors[first_color..].iter_mut()
+                .try_for_each(|color| {
+                    *color = self.read_color()?;
+                    Some(())
+                });
+            if read_points.is_none() || read_interior.is_none() || read_colors.is_none() {
+                break;
+            }
+            self.align();
+
+            let mut grid = [[(0.0, 0.0); 4]; 4];
+            for (&(i, j), &point) in BOUNDARY.iter().zip(&boundary) {
+                grid[i][j] = point;
+            }
+            if tensor {
+                for (&(i, j), &point) in INTERIOR.iter().zip(&interior) {
+                    grid[i][j] = point;
+                }
+            } else {
+                coons_interior(&mut grid);
+            }
+
+            triangles.extend(tessellate_patch(&grid, &colors));
+            previous = Some((boundary, colors));
+        }
+        triangles
+    }
+}
+
+/// Fills in the interior control points of a Coons patch so it can be
+/// evaluated as a tensor-product patch.
+fn coons_interior(p: &mut [[(f32, f32); 4]; 4]) {
+    let combine = |terms: &[(f32, (f32, f32))]| {
+        let (x, y) = terms.iter().fold((0.0, 0.0), |(x, y), (w, (px, py))| (x + w * px, y + w * py));
+        (x / 9.0, y / 9.0)
+    };
+    p[1][1] = combine(&[
+        (-4.0, p[0][0]), (6.0, p[0][1]), (6.0, p[1][0]), (-2.0, p[0][3]), (-2.0, p[3][0]),
+        (3.0, p[3][1]), (3.0, p[1][3]), (-1.0, p[3][3]),
+    ]);
+    p[1][2] = combine(&[
+        (-4.0, p[0][3]), (6.0, p[0][2]), (6.0, p[1][3]), (-2.0, p[0][0]), (-2.0, p[3][3]),
+        (3.0, p[3][2]), (3.0, p[1][0]), (-1.0, p[3][0]),
+    ]);
+    p[2][1] = combine(&[
+        (-4.0, p[3][0]), (6.0, p[3][1]), (6.0, p[2][0]), (-2.0, p[3][3]), (-2.0, p[0][0]),
+        (3.0, p[0][1]), (3.0, p[2][3]), (-1.0, p[0][3]),
+    ]);
+    p[2][2] = combine(&[
+        (-4.0, p[3][3]), (6.0, p[3][2]), (6.0, p[2][3]), (-2.0, p[3][0]), (-2.0, p[0][3]),
+        (3.0, p[0][2]), (3.0, p[2][0]), (-1.0, p[0][0]),
+    ]);
+}
+
+/// Evaluates a tensor-product patch on a grid, interpolating the corner
+/// colors (at p00, p03, p33 and p30) bilinearly.
+fn tessellate_patch(p: &[[(f32, f32); 4]; 4], colors: &[Vec<f32>; 4]) -> Vec<[RawVertex; 3]> {
+    let bernstein = |t: f32| {
+        let s = 1.0 - t;
+        [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t]
+    };
+
+    let grid: Vec<Vec<RawVertex>> = (0..=PATCH_GRID).map(|a| {
+        let u = a as f32 / PATCH_GRID as f32;
+        let bu = bernstein(u);
+        (0..=PATCH_GRID).map(|b| {
+            let v = b as f32 / PATCH_GRID as f32;
+            let bv = bernstein(v);
+            let (mut x, mut y) = (0.0, 0.0);
+            for i in 0..4 {
+                for j in 0..4 {
+                    x += p[i][j].0 * bu[i] * bv[j];
+                    y += p[i][j].1 * bu[i] * bv[j];
+                }
+            }
+            let weights = [(1.0 - u) * (1.0 - v), (1.0 - u) * v, u * v, u * (1.0 - v)];
+            let count = colors.iter().map(Vec::len).min().unwrap_or(0);
+            let components = (0..count)
+                .map(|c| colors.iter().zip(weights).map(|(color, w)| color[c] * w).sum())
+                .collect();
+            RawVertex { x, y, components }
+        }).collect()
+    }).collect();
+
+    grid_triangles(&grid)
+}
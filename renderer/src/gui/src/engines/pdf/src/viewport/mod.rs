@@ -51,12 +51,6 @@ impl ViewportManager {
         let scale_x = 2.0 / self.page_width;
         let scale_y = 2.0 / self.page_height;
 
-        web_sys::console::log_1(&format!(
-            "PDF size: {}x{}, scale: {}x{}", 
-            self.page_width, self.page_height,
-            scale_x, scale_y
-        ).into());
-
         // Move origin to top-left and flip Y axis
         [
             scale_x,  0.0,     0.0, 0.0,  // Scale X